@@ -0,0 +1,34 @@
+//! Default settings for new channels, saved per guild so that they survive
+//! restarts.
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{Dialect, GameBoard};
+
+/// The directory the defaults are stored in, within the data directory.
+pub const KIND: &str = "defaults";
+
+/// The settings of a channel that can be saved as the default of its guild.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Defaults {
+    /// The maximum number of steps any Brainfuck command is evaluated for.
+    pub steps: u32,
+
+    /// The game board, at its starting position.
+    pub board: GameBoard,
+
+    /// The variant of Brainfuck moves are written in.
+    pub dialect: Dialect,
+
+    /// Whether the players bid for the first move.
+    pub bidding: bool,
+
+    /// Whether the swap rule is in effect.
+    pub swap: bool,
+
+    /// Whether adjacent locks grant a bonus move.
+    pub adjacency: bool,
+
+    /// Whether the record of a game is posted when it ends.
+    pub pgn: bool,
+}
@@ -1,17 +1,52 @@
-use std::cmp::Ordering;
 use std::collections::{HashMap, VecDeque};
+use std::convert::TryFrom;
 use std::fmt::{Display, Formatter, Result as FmtResult, Write};
-use std::ops::Index;
+use std::iter::FromIterator;
+use std::ops::{Index, Range};
 use std::slice::Iter;
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Represents a player in the game.
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "char"))]
 pub struct Player(char);
 
+impl TryFrom<char> for Player {
+    type Error = InvalidPlayerChar;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        Self::new(c)
+    }
+}
+
 impl Player {
-    /// Initializes a new player with the given symbol.
-    pub fn new(c: char) -> Self {
-        Self(c)
+    /// The symbol for neutral counters, which take up space in a bucket but
+    /// belong to no player.
+    pub const NEUTRAL: Self = Self('#');
+
+    /// The characters that can't represent a player, since they're either
+    /// commands or part of the board display.
+    pub const RESERVED: &'static [char] = &[
+        '+', '-', '<', '>', '[', ']', '{', '}', '.', ',', '~', '^', '%', '@', '|', '_', '(', ')',
+    ];
+
+    /// Initializes a new player with the given symbol, which must be visible
+    /// and not reserved.
+    pub fn new(c: char) -> Result<Self, InvalidPlayerChar> {
+        if c.is_whitespace() || c.is_control() || Self::RESERVED.contains(&c) {
+            Err(InvalidPlayerChar { c })
+        } else {
+            Ok(Self(c))
+        }
+    }
+
+    /// Returns whether this represents a neutral counter.
+    pub fn is_neutral(self) -> bool {
+        self == Self::NEUTRAL
     }
 }
 
@@ -21,14 +56,60 @@ impl Display for Player {
     }
 }
 
+/// The error returned when a character can't represent a player.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidPlayerChar {
+    /// The invalid character.
+    pub c: char,
+}
+
+impl Display for InvalidPlayerChar {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f,
+            "{:?} is whitespace, a control character or reserved",
+            self.c
+        )
+    }
+}
+
+/// The error returned when a list of players contains some symbol twice.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DuplicatePlayerError {
+    /// The repeated symbol.
+    pub player: Player,
+}
+
+impl Display for DuplicatePlayerError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "repeated character {}", self.player)
+    }
+}
+
 /// The list of players in the game, in cyclic order.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "Vec<Player>"))]
 pub struct Players(Vec<Player>);
 
+impl TryFrom<Vec<Player>> for Players {
+    type Error = DuplicatePlayerError;
+
+    fn try_from(players: Vec<Player>) -> Result<Self, Self::Error> {
+        Self::new(players)
+    }
+}
+
 impl Players {
-    /// Initializes a new list of players.
-    pub fn new(players: Vec<Player>) -> Self {
-        Self(players)
+    /// Initializes a new list of players, which must all be distinct.
+    pub fn new(players: Vec<Player>) -> Result<Self, DuplicatePlayerError> {
+        for (idx, &player) in players.iter().enumerate() {
+            if players[idx + 1..].contains(&player) {
+                return Err(DuplicatePlayerError { player });
+            }
+        }
+
+        Ok(Self(players))
     }
 
     /// Returns the number of players in the game.
@@ -37,14 +118,66 @@ impl Players {
     }
 
     /// Returns the index of the current player, based on the turn number.
-    pub fn idx(&self, turn: usize) -> usize {
-        turn % self.len()
+    pub fn idx(&self, turn: u32) -> usize {
+        turn as usize % self.len()
+    }
+
+    /// Returns an iterator over the players.
+    pub fn iter(&self) -> Iter<Player> {
+        self.0.iter()
+    }
+
+    /// Returns whether the given player is in the list.
+    pub fn contains(&self, player: Player) -> bool {
+        self.0.contains(&player)
+    }
+
+    /// Swaps the players at the given indices.
+    pub fn swap(&mut self, a: usize, b: usize) {
+        self.0.swap(a, b)
+    }
+}
+
+impl IntoIterator for Players {
+    type Item = Player;
+    type IntoIter = std::vec::IntoIter<Player>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
     }
 }
 
+impl<'a> IntoIterator for &'a Players {
+    type Item = &'a Player;
+    type IntoIter = Iter<'a, Player>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// The number of buckets on the default board.
+pub const DEFAULT_BUCKET_COUNT: usize = 5;
+
+/// The capacity of each bucket on the default board.
+pub const DEFAULT_BUCKET_CAPACITY: usize = 10;
+
+/// The number of buckets that can remain unfilled on the default board.
+pub const DEFAULT_BUFFER_BUCKETS: u16 = 0;
+
+/// The default symbols for the players, in order.
+pub const DEFAULT_PLAYER_SYMBOLS: &[char] = &['X', 'O', 'Y', 'Z', 'W', 'V', 'U', 'T'];
+
 impl Default for Players {
     fn default() -> Self {
-        Self::new(vec![Player::new('X'), Player::new('O')])
+        // The default symbols are all valid.
+        Self(
+            DEFAULT_PLAYER_SYMBOLS[..2]
+                .iter()
+                .copied()
+                .map(Player)
+                .collect(),
+        )
     }
 }
 
@@ -57,7 +190,7 @@ impl Index<usize> for Players {
 }
 
 /// Represents the winners of a game.
-#[derive(Default)]
+#[derive(Debug, Default, PartialEq, Eq)]
 pub struct Winners(Vec<Player>);
 
 impl Index<usize> for Winners {
@@ -86,29 +219,48 @@ impl Display for Winners {
     }
 }
 
-impl Winners {
-    /// Initializes a winner list.
-    fn new(players: Vec<Player>) -> Self {
-        Self(players)
+impl IntoIterator for Winners {
+    type Item = Player;
+    type IntoIter = std::vec::IntoIter<Player>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl FromIterator<Player> for Winners {
+    fn from_iter<I: IntoIterator<Item = Player>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
     }
+}
 
-    /// Initializes a winner list with a single winner.
-    fn single(player: Player) -> Self {
-        Self::new(vec![player])
+impl Extend<Player> for Winners {
+    fn extend<I: IntoIterator<Item = Player>>(&mut self, iter: I) {
+        self.0.extend(iter)
     }
+}
 
+impl Winners {
     /// Returns the number of players that won.
-    fn winner_count(&self) -> usize {
+    pub fn winner_count(&self) -> usize {
         self.0.len()
     }
 
-    /// Pushes a player onto the winner list.
-    fn push(&mut self, player: Player) {
-        self.0.push(player)
+    /// Returns whether several players tied for the win.
+    pub fn is_draw(&self) -> bool {
+        self.winner_count() > 1
+    }
+
+    /// Returns the winner, unless several players tied.
+    pub fn sole_winner(&self) -> Option<Player> {
+        match self.0[..] {
+            [player] => Some(player),
+            _ => None,
+        }
     }
 
     /// Returns an iterator over the winners.
-    fn iter(&self) -> Iter<Player> {
+    pub fn iter(&self) -> Iter<Player> {
         self.0.iter()
     }
 
@@ -119,8 +271,9 @@ impl Winners {
 }
 
 /// A command to be executed by the [`Game`].
-#[derive(Clone, Copy)]
-enum Command {
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Command {
     /// Increments the value that's currently being pointed to.
     Increment,
 
@@ -132,10 +285,68 @@ enum Command {
 
     /// Moves the data pointer right.
     MoveRight,
+
+    /// Removes every counter from the current bucket. Only available with
+    /// extensions enabled.
+    Clear,
+
+    /// Moves the data pointer to the leftmost unlocked bucket. Only available
+    /// with extensions enabled.
+    Seek,
+
+    /// Switches to the other tape, whose pointer stays where it was left.
+    /// Only available with extensions enabled.
+    SwitchTape,
+
+    /// Moves the data pointer up a row of the grid. Only available with
+    /// extensions enabled.
+    MoveUp,
+
+    /// Moves the data pointer down a row of the grid. Only available with
+    /// extensions enabled.
+    MoveDown,
+}
+
+impl Command {
+    /// Returns the character for the command.
+    pub fn as_char(self) -> char {
+        match self {
+            Self::Increment => '+',
+            Self::Decrement => '-',
+            Self::MoveLeft => '<',
+            Self::MoveRight => '>',
+            Self::Clear => '~',
+            Self::Seek => '^',
+            Self::SwitchTape => '%',
+            Self::MoveUp => '(',
+            Self::MoveDown => ')',
+        }
+    }
+
+    /// Returns whether the command moves the data pointer.
+    fn is_move(self) -> bool {
+        matches!(
+            self,
+            Self::MoveLeft
+                | Self::MoveRight
+                | Self::Seek
+                | Self::SwitchTape
+                | Self::MoveUp
+                | Self::MoveDown
+        )
+    }
+}
+
+impl Display for Command {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}", self.as_char())
+    }
 }
 
 /// Any of the possible errors while parsing and running a Brainfuck program.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum EvalError {
     /// A bucket's fill exceeded its capacity.
     Overflow {
@@ -179,9 +390,76 @@ pub enum EvalError {
         idx: usize,
     },
 
+    /// A loop was opened with one kind of bracket and closed with another.
+    MixedBrackets {
+        /// The position of the left bracket in the string.
+        left: usize,
+
+        /// The position of the right bracket in the string.
+        right: usize,
+    },
+
+    /// Loops were nested more deeply than allowed.
+    TooDeep {
+        /// The position of the left bracket that exceeded the limit.
+        idx: usize,
+
+        /// The maximum nesting depth.
+        depth: usize,
+    },
+
+    /// A loop has an empty body, so it either does nothing or never ends.
+    TrivialLoop {
+        /// The position of its left bracket in the string.
+        idx: usize,
+    },
+
+    /// You attempted to add a counter, but had none left in your supply.
+    OutOfCounters {
+        /// The player who ran out of counters.
+        player: Player,
+    },
+
+    /// You attempted to move to the first unlocked bucket, but every bucket
+    /// was locked.
+    AllLocked,
+
+    /// You attempted to switch tapes, but the board only has one.
+    SingleTape,
+
+    /// You attempted to move up or down, but the board isn't a grid.
+    NotGrid,
+
+    /// You attempted to unlock a bucket that isn't locked by an opponent.
+    InvalidUnlock {
+        /// The position of the bucket.
+        position: usize,
+    },
+
+    /// A move was made out of turn.
+    WrongPlayer {
+        /// The player whose turn it was.
+        expected: Player,
+    },
+
+    /// You attempted to remove more counters than allowed.
+    DecrementLimit {
+        /// The maximum number of decrements.
+        limit: DecrementLimit,
+    },
+
+    /// You attempted to use a loop during the opening, while they're banned.
+    OpeningRestriction {
+        /// The first turn on which loops are allowed, starting from 0.
+        turn: u32,
+    },
+
     /// The computation went on for longer than allowed.
     MaxSteps,
 
+    /// A `,` was read after every value given as input had been used.
+    NoInput,
+
     /// The string has an invalid character.
     InvalidChar {
         /// The invalid character.
@@ -193,14 +471,37 @@ pub enum EvalError {
 
     /// The string is greater that can be at this specific turn.
     Length {
-        /// The length of the string.
+        /// The number of tokens in the program.
         len: usize,
 
+        /// The number of characters in the string, whitespace included.
+        chars: usize,
+
         /// The current turn number, i.e. the maximal string length.
-        turn: usize,
+        turn: u32,
     },
 }
 
+impl EvalError {
+    /// Passes every index into the program's string through the given
+    /// function, such as to point into the message the program was taken
+    /// from. Indices are passed in the order they're written in.
+    pub fn map_indices<F: FnMut(usize) -> usize>(self, mut f: F) -> Self {
+        match self {
+            Self::MismatchedLeft { idx } => Self::MismatchedLeft { idx: f(idx) },
+            Self::MismatchedRight { idx } => Self::MismatchedRight { idx: f(idx) },
+            Self::MixedBrackets { left, right } => Self::MixedBrackets {
+                left: f(left),
+                right: f(right),
+            },
+            Self::TooDeep { idx, depth } => Self::TooDeep { idx: f(idx), depth },
+            Self::TrivialLoop { idx } => Self::TrivialLoop { idx: f(idx) },
+            Self::InvalidChar { c, idx } => Self::InvalidChar { c, idx: f(idx) },
+            err => err,
+        }
+    }
+}
+
 impl Display for EvalError {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         match *self {
@@ -232,6 +533,26 @@ impl Display for EvalError {
                 write!(f, "mismatched right bracket at index {}", idx + 1)
             }
 
+            Self::MixedBrackets { left, right } => write!(
+                f,
+                "the loop opened at index {} is closed by a different kind of bracket at index {}",
+                left + 1,
+                right + 1
+            ),
+
+            Self::TooDeep { idx, depth } => write!(
+                f,
+                "the loop at index {} is nested more than {} loops deep",
+                idx + 1,
+                depth
+            ),
+
+            Self::TrivialLoop { idx } => write!(
+                f,
+                "the loop at index {} is empty, so it would never end if entered",
+                idx + 1
+            ),
+
             Self::LockedIncr { position } => {
                 write!(
                     f,
@@ -248,18 +569,60 @@ impl Display for EvalError {
                 )
             }
 
+            Self::OutOfCounters { player } => {
+                write!(f, "player {} has no counters left to place", player)
+            }
+
+            Self::AllLocked => {
+                write!(
+                    f,
+                    "you attempted to move to an unlocked bucket, but every bucket was locked"
+                )
+            }
+
+            Self::SingleTape => {
+                write!(f, "you attempted to switch tapes, but there's only one")
+            }
+
+            Self::NotGrid => {
+                write!(
+                    f,
+                    "you attempted to move up or down, but the board isn't a grid"
+                )
+            }
+
+            Self::InvalidUnlock { position } => write!(
+                f,
+                "you attempted to unlock bucket {}, but it isn't locked by an opponent",
+                position + 1
+            ),
+
+            Self::WrongPlayer { expected } => {
+                write!(f, "it was player {}'s turn", expected)
+            }
+
+            Self::OpeningRestriction { turn } => {
+                write!(f, "loops aren't allowed before turn {}", turn + 1)
+            }
+
+            Self::DecrementLimit { limit } => {
+                write!(f, "you exceeded the decrement limit of {}", limit)
+            }
+
             Self::MaxSteps => {
                 write!(f, "computation exceeded maximum number of steps")
             }
 
+            Self::NoInput => write!(f, "the move read more input than was given"),
+
             Self::InvalidChar { c, idx } => {
                 write!(f, "invalid character {} at index {}", c, idx + 1)
             }
 
-            Self::Length { len, turn } => write!(
+            Self::Length { len, chars, turn } => write!(
                 f,
-                "move was {} characters, must be {} characters or less",
-                len, turn
+                "move was {} tokens ({} chars), must be at most {} tokens",
+                len, chars, turn
             ),
         }
     }
@@ -270,508 +633,4477 @@ impl std::error::Error for EvalError {}
 /// The result of evaluating a Brainfuck program.
 pub type EvalResult<T> = Result<T, EvalError>;
 
-/// Represents a bucket in the game.
-#[derive(Debug)]
-pub struct Bucket {
-    /// The objects in the bucket, together with its capacity.
-    pub counters: Vec<Player>,
+/// Any of the possible errors while editing a board by hand.
+#[derive(Clone, Copy, Debug)]
+pub enum EditError {
+    /// There's no bucket at the given index.
+    NoBucket {
+        /// The index of the bucket.
+        position: usize,
+    },
 
-    /// Whether the bucket is locked, i.e. filled with counters from a single player.
-    pub locked: bool,
-}
+    /// The bucket doesn't have enough free space.
+    Overflow {
+        /// The index of the bucket.
+        position: usize,
+    },
 
-impl Clone for Bucket {
-    fn clone(&self) -> Self {
-        let mut data = Vec::with_capacity(self.capacity());
-        data.clone_from(&self.counters);
+    /// The bucket doesn't have enough counters.
+    Underflow {
+        /// The index of the bucket.
+        position: usize,
+    },
 
-        Self {
-            counters: data,
-            locked: self.locked,
-        }
-    }
+    /// The bucket has no player counters, so it can't be locked.
+    EmptyLock {
+        /// The index of the bucket.
+        position: usize,
+    },
+
+    /// The symbol doesn't belong to any player.
+    UnknownPlayer {
+        /// The symbol.
+        player: Player,
+    },
+
+    /// The number of labels doesn't match the number of buckets.
+    LabelCount {
+        /// The number of labels given.
+        labels: usize,
+
+        /// The number of buckets.
+        buckets: usize,
+    },
+
+    /// The grid doesn't have as many cells as there are buckets, or the
+    /// board has a second tape.
+    InvalidGrid {
+        /// The number of rows.
+        rows: usize,
+
+        /// The number of columns.
+        cols: usize,
+    },
 }
 
-impl Display for Bucket {
+impl Display for EditError {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        for team in &self.counters {
-            write!(f, "{}", team)?;
-        }
+        match *self {
+            Self::NoBucket { position } => write!(f, "there's no bucket {}", position + 1),
 
-        for _ in 0..self.free() {
-            f.write_char('_')?;
-        }
+            Self::Overflow { position } => {
+                write!(f, "bucket {} doesn't have enough space", position + 1)
+            }
 
-        write!(f, " {}/{}", self.fill(), self.capacity())?;
-        if self.locked {
-            f.write_str(" ✓")?;
-        }
+            Self::Underflow { position } => {
+                write!(f, "bucket {} doesn't have enough counters", position + 1)
+            }
 
-        Ok(())
-    }
-}
+            Self::EmptyLock { position } => {
+                write!(
+                    f,
+                    "bucket {} has no player counters, so it can't be locked",
+                    position + 1
+                )
+            }
 
-impl Bucket {
-    /// Initializes a new, empty bucket with the specified capacity.
-    pub fn new(capacity: usize) -> Self {
-        Self {
-            counters: Vec::with_capacity(capacity),
-            locked: false,
-        }
-    }
+            Self::UnknownPlayer { player } => write!(f, "there's no player {}", player),
 
-    /// Empties the bucket.
-    fn empty(&mut self) {
-        *self = Self::new(self.capacity());
-    }
+            Self::LabelCount { labels, buckets } => {
+                write!(f, "got {} labels for {} buckets", labels, buckets)
+            }
 
-    /// Returns the fill of the bucket.
-    fn fill(&self) -> usize {
-        self.counters.len()
+            Self::InvalidGrid { rows, cols } => write!(
+                f,
+                "a {}x{} grid needs exactly {} buckets on a single tape",
+                rows,
+                cols,
+                rows * cols
+            ),
+        }
     }
+}
 
-    /// Returns whether the bucket is empty.
-    fn is_empty(&self) -> bool {
-        self.fill() == 0
-    }
+impl std::error::Error for EditError {}
 
-    /// Returns the capacity of the bucket.
-    fn capacity(&self) -> usize {
-        self.counters.capacity()
-    }
+/// Any of the possible errors while parsing a board.
+#[derive(Clone, Debug)]
+pub enum BoardParseError {
+    /// The first line isn't of the form `Turn N -- X to move`.
+    InvalidHeader,
 
-    /// Returns the amount of free spaces in the bucket.
-    fn free(&self) -> usize {
-        self.capacity() - self.fill()
-    }
+    /// A bucket line couldn't be parsed.
+    InvalidBucket {
+        /// The index of the bucket.
+        position: usize,
+    },
 
-    /// Pushes the specified player's counter onto the bucket. Returns `true` if successful.
-    fn push(&mut self, player: Player, position: usize) -> EvalResult<()> {
-        match self.free() {
-            0 => {
-                return Err(if self.locked {
-                    EvalError::LockedIncr { position }
-                } else {
-                    EvalError::Overflow { position }
-                })
-            }
+    /// A bucket line describes an impossible bucket.
+    ImpossibleBucket {
+        /// The index of the bucket.
+        position: usize,
 
-            1 => {
-                self.counters.push(player);
+        /// The reason the bucket is impossible.
+        reason: String,
+    },
 
-                for &counter in self.counters.iter() {
-                    if counter != player {
-                        return Ok(());
-                    }
-                }
+    /// The board has no buckets.
+    NoBuckets,
 
-                self.locked = true;
-            }
+    /// No bucket, or more than one, is marked with the pointer.
+    InvalidPointer,
 
-            _ => {
-                self.counters.push(player);
-            }
-        }
+    /// The tape headers are out of order, or don't say which tape is active.
+    InvalidTapes,
 
-        Ok(())
-    }
+    /// The row headers are out of order, the rows have different lengths,
+    /// or the board also has tape headers.
+    InvalidRows,
 
-    /// Pops the last element from the bucket. Returns `true` if succesful.
-    fn pop(&mut self, position: usize) -> EvalResult<()> {
-        if self.is_empty() {
-            Err(EvalError::Underflow { position })
-        } else if self.locked {
-            Err(EvalError::LockedDecr { position })
-        } else {
-            self.counters.pop().unwrap();
-            Ok(())
+    /// The symbols on the board aren't those of a default list of players
+    /// whose turn it could be.
+    UnknownPlayers,
+}
+
+impl Display for BoardParseError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Self::InvalidHeader => write!(f, "expected a line like \"Turn 1 -- X to move\""),
+            Self::InvalidBucket { position } => {
+                write!(f, "bucket {} could not be parsed", position + 1)
+            }
+            Self::ImpossibleBucket { position, reason } => {
+                write!(f, "bucket {} is impossible: {}", position + 1, reason)
+            }
+            Self::NoBuckets => write!(f, "the board has no buckets"),
+            Self::InvalidPointer => write!(f, "exactly one bucket must have the pointer"),
+            Self::InvalidTapes => write!(
+                f,
+                "expected a \"Tape 1:\" and a \"Tape 2:\" header, exactly one of them active"
+            ),
+            Self::InvalidRows => write!(
+                f,
+                "expected \"Row 1:\", \"Row 2:\", ... headers over rows of the same length"
+            ),
+            Self::UnknownPlayers => write!(f, "the players could not be determined"),
         }
     }
 }
 
-/// One of the possible brainfuck instructions, after being parsed.
-#[derive(Clone, Copy)]
-enum BrainfuckToken {
-    /// Execute a command, move the pointer to the right.
-    Command { cmd: Command },
+impl std::error::Error for BoardParseError {}
 
-    /// Move the pointer to the target if the data that's being pointed to is zero.
-    JumpIfZero { target: usize },
+/// Any of the possible errors while parsing a board specification.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SpecError {
+    /// The specification has no buckets.
+    Empty,
 
-    /// Move the pointer to the target if the data that's being pointed to is non-zero.
-    JumpIfNonzero { target: usize },
-}
+    /// The capacity in some group couldn't be parsed.
+    InvalidCapacity {
+        /// The group that failed to parse.
+        token: String,
+    },
+
+    /// The number of buckets in some group couldn't be parsed.
+    InvalidCount {
+        /// The group that failed to parse.
+        token: String,
+    },
+
+    /// The specification has too many buckets.
+    TooManyBuckets,
+}
+
+impl Display for SpecError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Self::Empty => write!(f, "no buckets were specified"),
+            Self::InvalidCapacity { token } => {
+                write!(f, "\"{}\" doesn't start with a valid capacity", token)
+            }
+            Self::InvalidCount { token } => {
+                write!(f, "\"{}\" doesn't end with a valid bucket count", token)
+            }
+            Self::TooManyBuckets => write!(f, "there can be at most {} buckets", u16::MAX),
+        }
+    }
+}
+
+impl std::error::Error for SpecError {}
+
+/// Parses a board specification into a list of capacities. The
+/// specification is a list of groups separated by commas, each either a
+/// single capacity, or of the form `<capacity>x<count>` for several buckets
+/// with the same capacity. For instance, `10x5,5x3` stands for five buckets of
+/// capacity 10, followed by three of capacity 5.
+pub fn parse_spec(spec: &str) -> Result<Vec<usize>, SpecError> {
+    let mut capacities = Vec::new();
+
+    for token in spec.split(',').map(str::trim) {
+        let (capacity, count) = match token.split_once('x') {
+            Some((capacity, count)) => (capacity, Some(count)),
+            None => (token, None),
+        };
+
+        let capacity = capacity
+            .parse::<usize>()
+            .map_err(|_| SpecError::InvalidCapacity {
+                token: token.to_owned(),
+            })?;
+
+        let count = match count {
+            Some(count) => count
+                .parse::<usize>()
+                .map_err(|_| SpecError::InvalidCount {
+                    token: token.to_owned(),
+                })?,
+            None => 1,
+        };
+
+        // Checks the count before allocating anything.
+        if count > u16::MAX as usize - capacities.len() {
+            return Err(SpecError::TooManyBuckets);
+        }
+
+        capacities.resize(capacities.len() + count, capacity);
+    }
+
+    if capacities.is_empty() {
+        Err(SpecError::Empty)
+    } else {
+        Ok(capacities)
+    }
+}
+
+/// Represents a bucket in the game.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "BucketData", into = "BucketData"))]
+pub struct Bucket {
+    /// The objects in the bucket.
+    pub counters: Vec<Player>,
+
+    /// The number of counters the bucket can hold.
+    capacity: usize,
+
+    /// Whether the bucket is locked, i.e. filled with counters from a single player.
+    pub locked: bool,
+
+    /// The player who owns the bucket, set once it becomes locked.
+    pub owner: Option<Player>,
+
+    /// Whether the bucket was locked by an edit regardless of its contents,
+    /// in which case it needn't be full.
+    pub forced: bool,
+
+    /// The name shown for the bucket, which is purely cosmetic.
+    pub label: Option<String>,
+}
+
+impl PartialEq for Bucket {
+    fn eq(&self, other: &Self) -> bool {
+        // Labels are cosmetic, so they're ignored.
+        self.capacity == other.capacity
+            && self.counters == other.counters
+            && self.locked == other.locked
+            && self.owner == other.owner
+            && self.forced == other.forced
+    }
+}
+
+impl Eq for Bucket {}
+
+/// How the counters on the board are drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DisplayMode {
+    /// Counters are drawn as the symbols of their players.
+    #[default]
+    Plain,
+
+    /// Counters are drawn as colored squares, by the seat of their players.
+    Emoji,
+}
+
+impl Display for DisplayMode {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Self::Plain => write!(f, "plain"),
+            Self::Emoji => write!(f, "emoji"),
+        }
+    }
+}
+
+impl DisplayMode {
+    /// The squares for the counters of each seat, in order.
+    const COLORS: [&'static str; 7] = ["🟥", "🟦", "🟩", "🟨", "🟪", "🟧", "🟫"];
+
+    /// The square for neutral counters.
+    const NEUTRAL: &'static str = "⬛";
+
+    /// The square for free spaces.
+    const EMPTY: &'static str = "⬜";
+
+    /// Returns how a counter of the given player is drawn. Players without a
+    /// color of their own are drawn as their symbols.
+    fn counter(self, player: Player, players: &Players) -> String {
+        if self == Self::Plain {
+            return player.to_string();
+        }
+
+        if player.is_neutral() {
+            return Self::NEUTRAL.to_owned();
+        }
+
+        players
+            .iter()
+            .position(|&p| p == player)
+            .and_then(|idx| Self::COLORS.get(idx))
+            .map_or_else(|| player.to_string(), |&color| color.to_owned())
+    }
+}
+
+/// A board together with the mode to display it in.
+pub struct BoardView<'a> {
+    /// The board to display.
+    board: &'a GameBoard,
+
+    /// How the counters are drawn.
+    mode: DisplayMode,
+}
+
+impl Display for BoardView<'_> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        self.board.fmt_with(f, self.mode)
+    }
+}
+
+/// The rule that decides who owns a full bucket.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum LockRule {
+    /// A full bucket locks if all of its counters belong to a single player.
+    #[default]
+    Uniform,
+
+    /// A full bucket locks for whichever player holds a strict majority of its
+    /// counters.
+    Majority,
+}
+
+impl Display for LockRule {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Self::Uniform => write!(f, "uniform"),
+            Self::Majority => write!(f, "majority"),
+        }
+    }
+}
+
+impl LockRule {
+    /// Returns the owner of a full bucket with the given counters, if any.
+    /// Neutral counters never own a bucket.
+    fn owner(self, counters: &[Player]) -> Option<Player> {
+        let first = *counters.first()?;
+
+        match self {
+            Self::Uniform => {
+                (!first.is_neutral() && counters.iter().all(|&c| c == first)).then_some(first)
+            }
+
+            Self::Majority => counters
+                .iter()
+                .copied()
+                .filter(|c| !c.is_neutral())
+                .find(|&p| 2 * counters.iter().filter(|&&c| c == p).count() > counters.len()),
+        }
+    }
+}
+
+/// The serialized form of a [`Bucket`], which stores its capacity explicitly.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct BucketData {
+    /// The counters in the bucket.
+    counters: Vec<Player>,
+
+    /// The capacity of the bucket.
+    capacity: usize,
+
+    /// Whether the bucket is locked.
+    #[cfg_attr(feature = "serde", serde(default))]
+    locked: bool,
+
+    /// The player who owns the bucket.
+    #[cfg_attr(feature = "serde", serde(default))]
+    owner: Option<Player>,
+
+    /// Whether the bucket was locked by an edit.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    forced: bool,
+
+    /// The name shown for the bucket.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    label: Option<String>,
+}
+
+#[cfg(feature = "serde")]
+impl From<Bucket> for BucketData {
+    fn from(bucket: Bucket) -> Self {
+        Self {
+            capacity: bucket.capacity(),
+            counters: bucket.counters,
+            locked: bucket.locked,
+            owner: bucket.owner,
+            forced: bucket.forced,
+            label: bucket.label,
+        }
+    }
+}
+
+impl TryFrom<BucketData> for Bucket {
+    type Error = String;
+
+    fn try_from(data: BucketData) -> Result<Self, Self::Error> {
+        if data.counters.len() > data.capacity {
+            return Err(format!(
+                "bucket has {} counters but a capacity of {}",
+                data.counters.len(),
+                data.capacity
+            ));
+        }
+
+        if data.locked != data.owner.is_some() {
+            return Err("bucket must have an owner exactly when it's locked".to_owned());
+        }
+
+        if data.forced && !data.locked {
+            return Err("only a locked bucket can be force-locked".to_owned());
+        }
+
+        if let Some(owner) = data.owner {
+            if owner.is_neutral() {
+                return Err("bucket can't be owned by neutral counters".to_owned());
+            }
+
+            // Buckets locked by an edit may hold anything.
+            if !data.forced && data.counters.len() != data.capacity
+                || LockRule::Majority.owner(&data.counters) != Some(owner)
+            {
+                return Err(
+                    "locked bucket must be full, with a majority of its owner's counters"
+                        .to_owned(),
+                );
+            }
+        }
+
+        let mut bucket = Self::new(data.capacity);
+        bucket.counters.extend(data.counters);
+        bucket.locked = data.locked;
+        bucket.owner = data.owner;
+        bucket.forced = data.forced;
+        bucket.label = data.label;
+        Ok(bucket)
+    }
+}
+
+impl Display for Bucket {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        if let Some(label) = &self.label {
+            write!(f, "[{}] ", label)?;
+        }
+
+        self.fmt_contents(f)
+    }
+}
+
+impl Bucket {
+    /// Writes the counters, the fill and the owner of the bucket, without its
+    /// label.
+    fn fmt_contents(&self, f: &mut Formatter) -> FmtResult {
+        for team in &self.counters {
+            write!(f, "{}", team)?;
+        }
+
+        for _ in 0..self.free() {
+            f.write_char('_')?;
+        }
+
+        write!(f, " {}/{}", self.fill(), self.capacity())?;
+        if let Some(owner) = self.owner {
+            write!(f, " ✓ {}", owner)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the contents of the bucket like [`Self::fmt_contents`], but
+    /// drawing the counters and free spaces as specified by the display mode.
+    pub fn fmt_rich(&self, f: &mut Formatter, mode: DisplayMode, players: &Players) -> FmtResult {
+        if mode == DisplayMode::Plain {
+            return self.fmt_contents(f);
+        }
+
+        for &team in &self.counters {
+            write!(f, "{}", mode.counter(team, players))?;
+        }
+
+        for _ in 0..self.free() {
+            write!(f, "{}", DisplayMode::EMPTY)?;
+        }
+
+        write!(f, " {}/{}", self.fill(), self.capacity())?;
+        if let Some(owner) = self.owner {
+            write!(f, " ✓ {}", mode.counter(owner, players))?;
+        }
+
+        Ok(())
+    }
+
+    /// Initializes a new, empty bucket with the specified capacity.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            counters: Vec::with_capacity(capacity),
+            capacity,
+            locked: false,
+            owner: None,
+            forced: false,
+            label: None,
+        }
+    }
+
+    /// Empties the bucket, keeping its label.
+    fn empty(&mut self) {
+        let label = self.label.take();
+        *self = Self::new(self.capacity());
+        self.label = label;
+    }
+
+    /// Returns the fill of the bucket.
+    pub fn fill(&self) -> usize {
+        self.counters.len()
+    }
+
+    /// Returns whether the bucket is empty.
+    pub fn is_empty(&self) -> bool {
+        self.fill() == 0
+    }
+
+    /// Returns the capacity of the bucket.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Lowers the capacity by one, unless that would drop it below the fill
+    /// or below one. If the bucket becomes full, it's locked according to the
+    /// given rule. Returns whether the capacity changed.
+    fn shrink(&mut self, rule: LockRule) -> bool {
+        if self.capacity <= self.fill().max(1) {
+            return false;
+        }
+
+        self.capacity -= 1;
+        self.update_lock(rule);
+        true
+    }
+
+    /// Returns the amount of free spaces in the bucket.
+    pub fn free(&self) -> usize {
+        self.capacity() - self.fill()
+    }
+
+    /// Pushes the specified player's counter onto the bucket, locking it
+    /// according to the given rule if it becomes full.
+    fn push(&mut self, player: Player, position: usize, rule: LockRule) -> EvalResult<()> {
+        // A forced lock may leave free spaces, which can't be filled.
+        if self.locked {
+            return Err(EvalError::LockedIncr { position });
+        }
+
+        match self.free() {
+            0 => return Err(EvalError::Overflow { position }),
+
+            1 => {
+                self.counters.push(player);
+                self.update_lock(rule);
+            }
+
+            _ => {
+                self.counters.push(player);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Pops the last element from the bucket. Returns the popped counter if
+    /// succesful.
+    fn pop(&mut self, position: usize) -> EvalResult<Player> {
+        if self.is_empty() {
+            Err(EvalError::Underflow { position })
+        } else if self.locked {
+            Err(EvalError::LockedDecr { position })
+        } else {
+            Ok(self.counters.pop().unwrap())
+        }
+    }
+
+    /// Recomputes whether the bucket is locked, i.e. whether it's full and
+    /// some player owns it under the given rule.
+    fn update_lock(&mut self, rule: LockRule) {
+        self.owner = if self.free() == 0 {
+            rule.owner(&self.counters)
+        } else {
+            None
+        };
+
+        self.locked = self.owner.is_some();
+        self.forced = false;
+    }
+
+    /// Locks the bucket regardless of its contents, giving it to the player
+    /// with the topmost counter.
+    fn force_lock(&mut self, position: usize) -> Result<(), EditError> {
+        let owner = self
+            .counters
+            .iter()
+            .rev()
+            .copied()
+            .find(|c| !c.is_neutral());
+        self.owner = Some(owner.ok_or(EditError::EmptyLock { position })?);
+        self.locked = true;
+        self.forced = true;
+        Ok(())
+    }
+}
+
+/// One of the possible brainfuck instructions, after being parsed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BrainfuckToken {
+    /// Execute a command some number of times, move the pointer to the right.
+    Command {
+        /// The command to execute.
+        cmd: Command,
+
+        /// How many times in a row the command appears in the program.
+        count: usize,
+    },
+
+    /// Move the pointer to the target if the data that's being pointed to is zero.
+    JumpIfZero {
+        /// The index of the matching right bracket.
+        target: usize,
+    },
+
+    /// Move the pointer to the target if the data that's being pointed to is non-zero.
+    JumpIfNonzero {
+        /// The index of the matching left bracket.
+        target: usize,
+    },
+
+    /// Move the pointer to the target unless the top counter of the current
+    /// bucket belongs to the player to move. Only available with extensions
+    /// enabled.
+    JumpIfNotMine {
+        /// The index of the matching right brace.
+        target: usize,
+    },
+
+    /// Move the pointer to the target if the top counter of the current
+    /// bucket belongs to the player to move. Only available with extensions
+    /// enabled.
+    JumpIfMine {
+        /// The index of the matching left brace.
+        target: usize,
+    },
+
+    /// Move the data pointer to the given bucket. It's written with the
+    /// bucket's number, counting from 1. Only available with extensions
+    /// enabled.
+    Teleport {
+        /// The index of the bucket.
+        target: usize,
+    },
+
+    /// Write the counters in the current bucket to the output. Only available
+    /// with I/O enabled.
+    Output,
+
+    /// Add as many counters to the current bucket as the next input value.
+    /// Only available with I/O enabled.
+    Input,
+}
 
 impl From<Command> for BrainfuckToken {
     fn from(cmd: Command) -> Self {
-        Self::Command { cmd }
+        Self::Command { cmd, count: 1 }
     }
 }
 
-/// Represents a Brainfuck program.
-struct Brainfuck {
-    /// The different tokens that make up the program.
-    tokens: Vec<BrainfuckToken>,
+impl BrainfuckToken {
+    /// Returns the number of characters the token stands for, not counting
+    /// the digits of a teleport.
+    fn len(&self) -> usize {
+        match self {
+            Self::Command { count, .. } => *count,
+            _ => 1,
+        }
+    }
+}
 
-    /// The data pointer, which represents the index of the token that's currently being read.
-    pointer: usize,
+impl Display for BrainfuckToken {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Self::Command { cmd, count } => {
+                for _ in 0..*count {
+                    write!(f, "{}", cmd)?;
+                }
+
+                Ok(())
+            }
+            Self::JumpIfZero { .. } => write!(f, "["),
+            Self::JumpIfNonzero { .. } => write!(f, "]"),
+            Self::JumpIfNotMine { .. } => write!(f, "{{"),
+            Self::JumpIfMine { .. } => write!(f, "}}"),
+            Self::Teleport { target } => write!(f, "@{}", target + 1),
+            Self::Output => write!(f, "."),
+            Self::Input => write!(f, ","),
+        }
+    }
 }
 
-impl Brainfuck {
-    /// Tokenizes a string.
-    fn new(str: &str) -> EvalResult<Self> {
-        let mut queue = VecDeque::new();
-        let mut tokens = Vec::new();
+/// What a character in a Brainfuck program stands for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BfMeaning {
+    /// A command.
+    Command(Command),
+
+    /// The start of a loop.
+    LoopStart,
+
+    /// The end of a loop.
+    LoopEnd,
+}
+
+impl BfMeaning {
+    /// Every meaning, in the order of the standard characters `+-<>[]`.
+    pub const ALL: [Self; 6] = [
+        Self::Command(Command::Increment),
+        Self::Command(Command::Decrement),
+        Self::Command(Command::MoveLeft),
+        Self::Command(Command::MoveRight),
+        Self::LoopStart,
+        Self::LoopEnd,
+    ];
+
+    /// Returns the meaning of a character in standard Brainfuck.
+    fn from_standard(c: char) -> Option<Self> {
+        Some(match c {
+            '+' => Self::Command(Command::Increment),
+            '-' => Self::Command(Command::Decrement),
+            '<' => Self::Command(Command::MoveLeft),
+            '>' => Self::Command(Command::MoveRight),
+            '[' => Self::LoopStart,
+            ']' => Self::LoopEnd,
+            _ => return None,
+        })
+    }
+}
+
+/// The characters a Brainfuck program may be written with.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BfCharset {
+    /// The standard characters `+-<>[]`.
+    #[default]
+    Standard,
+
+    /// Custom characters, which take precedence over the standard ones.
+    Custom(HashMap<char, BfMeaning>),
+}
+
+impl Display for BfCharset {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Self::Standard => write!(f, "standard"),
+            Self::Custom(_) => {
+                for (idx, &meaning) in BfMeaning::ALL.iter().enumerate() {
+                    if idx != 0 {
+                        write!(f, " ")?;
+                    }
+
+                    write!(f, "{}", self.symbol(meaning))?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl BfCharset {
+    /// Builds a custom charset from the characters used for `+-<>[]`, in that
+    /// order. Returns `None` if any character is repeated or is whitespace.
+    pub fn custom(chars: [char; 6]) -> Option<Self> {
+        let mut map = HashMap::new();
+
+        for (&c, &meaning) in chars.iter().zip(BfMeaning::ALL.iter()) {
+            if c.is_whitespace() || map.insert(c, meaning).is_some() {
+                return None;
+            }
+        }
+
+        Some(Self::Custom(map))
+    }
+
+    /// Returns the character that stands for the given meaning.
+    pub fn symbol(&self, meaning: BfMeaning) -> char {
+        let standard = || {
+            let idx = BfMeaning::ALL.iter().position(|&m| m == meaning).unwrap();
+            "+-<>[]".chars().nth(idx).unwrap()
+        };
+
+        match self {
+            Self::Standard => standard(),
+            Self::Custom(map) => map
+                .iter()
+                .find(|&(_, &m)| m == meaning)
+                .map_or_else(standard, |(&c, _)| c),
+        }
+    }
+
+    /// Rewrites a program in standard Brainfuck with the characters of this
+    /// charset. Other characters are kept as they are.
+    pub fn translate(&self, code: &str) -> String {
+        code.chars()
+            .map(|c| BfMeaning::from_standard(c).map_or(c, |meaning| self.symbol(meaning)))
+            .collect()
+    }
+
+    /// Returns the meaning of a character, if any.
+    pub fn meaning(&self, c: char) -> Option<BfMeaning> {
+        match self {
+            Self::Standard => BfMeaning::from_standard(c),
+            Self::Custom(map) => map.get(&c).copied().or_else(|| BfMeaning::from_standard(c)),
+        }
+    }
+}
+
+/// The variant of Brainfuck that moves are written in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Dialect {
+    /// The characters of the commands.
+    pub charset: BfCharset,
+
+    /// Whether the extended commands, such as `@<n>` to move the pointer to
+    /// bucket `n`, are available.
+    pub extensions: bool,
+
+    /// The maximum number of loops that may be nested inside each other.
+    pub max_nesting: usize,
+
+    /// Whether `.` writes the contents of the current bucket to the output,
+    /// and `,` reads from the values given after a `|` at the end of a move.
+    pub io: bool,
+}
+
+impl Default for Dialect {
+    fn default() -> Self {
+        Self {
+            charset: BfCharset::default(),
+            extensions: false,
+            max_nesting: Self::MAX_NESTING,
+            io: false,
+        }
+    }
+}
+
+impl Dialect {
+    /// The default maximum nesting depth of loops.
+    pub const MAX_NESTING: usize = 16;
+}
+
+/// The memory a Brainfuck program runs on, such as a [`GameBoard`].
+pub trait Memory {
+    /// Executes a command the given number of times in a row.
+    fn exec_n(&mut self, cmd: Command, count: usize) -> EvalResult<()>;
+
+    /// Returns whether the current cell is empty.
+    fn is_empty(&self) -> bool;
+
+    /// Returns whether the current cell belongs to the player to move.
+    fn is_mine(&self) -> bool;
+
+    /// Moves the pointer to the given cell.
+    fn jump_to(&mut self, idx: usize) -> EvalResult<()>;
+
+    /// Writes the current cell to the output.
+    fn write_output(&mut self);
+
+    /// Reads a value from the input into the current cell, or handles the
+    /// end of the input if it's `None`.
+    fn read_input(&mut self, value: Option<usize>) -> EvalResult<()>;
+}
+
+/// Represents a Brainfuck program.
+#[derive(Clone, Debug)]
+pub struct Brainfuck {
+    /// The different tokens that make up the program.
+    tokens: Vec<BrainfuckToken>,
+
+    /// The number of characters in the original string, whitespace included.
+    chars: usize,
+
+    /// The data pointer, which represents the index of the token that's currently being read.
+    pointer: usize,
+
+    /// The values left to be read by `,`.
+    input: VecDeque<usize>,
+}
+
+impl Brainfuck {
+    /// Tokenizes a string. The indices in any errors refer to characters in
+    /// the original string, whitespace included.
+    pub fn new<S: AsRef<str>>(str: S) -> EvalResult<Self> {
+        Self::with_dialect(str, &Dialect::default())
+    }
+
+    /// Tokenizes a sequence of characters, such as one built by hand rather
+    /// than typed. Indices in errors work as in [`Brainfuck::new`].
+    pub fn from_chars<I: IntoIterator<Item = char>>(chars: I) -> EvalResult<Self> {
+        Self::new(chars.into_iter().collect::<String>())
+    }
+
+    /// Tokenizes a string written in the given dialect. With I/O enabled, the
+    /// string may end in a `|` followed by the input values.
+    pub fn with_dialect<S: AsRef<str>>(str: S, dialect: &Dialect) -> EvalResult<Self> {
+        let str = str.as_ref();
+
+        // Splits off the input, which isn't part of the program.
+        let (str, input_str) = match str.find('|') {
+            Some(split) if dialect.io && dialect.charset.meaning('|').is_none() => {
+                (&str[..split], Some(&str[split + 1..]))
+            }
+            _ => (str, None),
+        };
+
+        // Stores the token indices of the unmatched left brackets, together
+        // with their indices in the string.
+        let mut queue = VecDeque::new();
+        let mut tokens = Vec::new();
+
+        // Iterates over non-whitespace characters.
+        let mut chars = str
+            .chars()
+            .enumerate()
+            .filter(|(_, c)| !c.is_whitespace())
+            .peekable();
+
+        while let Some((idx, c)) = chars.next() {
+            let pos = tokens.len();
+
+            // Reads the bucket number after a teleport, counting from 1.
+            if dialect.extensions && c == '@' {
+                let mut target = None;
+
+                while let Some(digit) = chars.peek().and_then(|(_, d)| d.to_digit(10)) {
+                    chars.next();
+                    target = target
+                        .unwrap_or(0usize)
+                        .checked_mul(10)
+                        .and_then(|t| t.checked_add(digit as usize));
+
+                    if target.is_none() {
+                        return Err(EvalError::InvalidChar { c, idx });
+                    }
+                }
+
+                match target.and_then(|t| t.checked_sub(1)) {
+                    Some(target) => tokens.push(BrainfuckToken::Teleport { target }),
+                    None => return Err(EvalError::InvalidChar { c, idx }),
+                }
+
+                continue;
+            }
+
+            if dialect.extensions && c == '~' {
+                tokens.push(Command::Clear.into());
+                continue;
+            }
+
+            if dialect.extensions && c == '^' {
+                tokens.push(Command::Seek.into());
+                continue;
+            }
+
+            if dialect.extensions && c == '%' {
+                tokens.push(Command::SwitchTape.into());
+                continue;
+            }
+
+            // Vertical moves are folded like horizontal ones.
+            if dialect.extensions && (c == '(' || c == ')') {
+                let cmd = if c == '(' {
+                    Command::MoveUp
+                } else {
+                    Command::MoveDown
+                };
+
+                match tokens.last_mut() {
+                    Some(BrainfuckToken::Command { cmd: last, count }) if *last == cmd => {
+                        *count += 1
+                    }
+                    _ => tokens.push(cmd.into()),
+                }
+
+                continue;
+            }
+
+            // Ownership loops are handled like regular ones, but remember
+            // their kind so that it can be matched.
+            let (meaning, owned) = match dialect.charset.meaning(c) {
+                None if dialect.extensions && c == '{' => (Some(BfMeaning::LoopStart), true),
+                None if dialect.extensions && c == '}' => (Some(BfMeaning::LoopEnd), true),
+                meaning => (meaning, false),
+            };
+
+            match meaning {
+                // Runs of the same command are folded into a single token.
+                Some(BfMeaning::Command(cmd)) => match tokens.last_mut() {
+                    Some(BrainfuckToken::Command { cmd: last, count }) if *last == cmd => {
+                        *count += 1
+                    }
+                    _ => tokens.push(cmd.into()),
+                },
+
+                Some(BfMeaning::LoopStart) => {
+                    if queue.len() == dialect.max_nesting {
+                        return Err(EvalError::TooDeep {
+                            idx,
+                            depth: dialect.max_nesting,
+                        });
+                    }
+
+                    tokens.push(if owned {
+                        BrainfuckToken::JumpIfNotMine { target: 0 }
+                    } else {
+                        BrainfuckToken::JumpIfZero { target: 0 }
+                    });
+                    queue.push_back((pos, idx, owned))
+                }
+
+                Some(BfMeaning::LoopEnd) => {
+                    if let Some((target, start, start_owned)) = queue.pop_back() {
+                        if owned != start_owned {
+                            return Err(EvalError::MixedBrackets {
+                                left: start,
+                                right: idx,
+                            });
+                        }
+
+                        // A loop with nothing inside can't ever finish.
+                        if target + 1 == pos {
+                            return Err(EvalError::TrivialLoop { idx: start });
+                        }
+
+                        tokens.push(if owned {
+                            BrainfuckToken::JumpIfMine { target }
+                        } else {
+                            BrainfuckToken::JumpIfNonzero { target }
+                        });
+
+                        match &mut tokens[target] {
+                            BrainfuckToken::JumpIfZero { target: old_target }
+                            | BrainfuckToken::JumpIfNotMine { target: old_target } => {
+                                *old_target = pos
+                            }
+                            _ => unreachable!(),
+                        }
+                    } else {
+                        return Err(EvalError::MismatchedRight { idx });
+                    }
+                }
+
+                None if dialect.io && c == '.' => tokens.push(BrainfuckToken::Output),
+                None if dialect.io && c == ',' => tokens.push(BrainfuckToken::Input),
+
+                None => {
+                    return Err(EvalError::InvalidChar { c, idx });
+                }
+            }
+        }
+
+        if let Some((_, idx, _)) = queue.pop_back() {
+            return Err(EvalError::MismatchedLeft { idx });
+        }
+
+        // Reads the input values, separated by whitespace.
+        let chars = str.chars().count();
+        let mut input = VecDeque::new();
+        let mut value: Option<usize> = None;
+
+        for (idx, c) in input_str.unwrap_or_default().chars().enumerate() {
+            let idx = chars + 1 + idx;
+
+            if let Some(digit) = c.to_digit(10) {
+                value = value
+                    .unwrap_or(0)
+                    .checked_mul(10)
+                    .and_then(|v| v.checked_add(digit as usize));
+
+                if value.is_none() {
+                    return Err(EvalError::InvalidChar { c, idx });
+                }
+            } else if c.is_whitespace() {
+                input.extend(value.take());
+            } else {
+                return Err(EvalError::InvalidChar { c, idx });
+            }
+        }
+
+        input.extend(value);
+        Ok(Self {
+            tokens,
+            chars,
+            pointer: 0,
+            input,
+        })
+    }
+
+    /// Replaces the values left to be read by `,`.
+    pub fn set_input<I: IntoIterator<Item = usize>>(&mut self, input: I) {
+        self.input = input.into_iter().collect();
+    }
+
+    /// Returns the tokens that make up the program.
+    pub fn tokens(&self) -> &[BrainfuckToken] {
+        &self.tokens
+    }
+
+    /// Formats the program with every loop on its own indented block. Commands
+    /// are kept on the same line until the pointer moves.
+    pub fn pretty_print(&self) -> String {
+        let mut res = String::new();
+        let mut indent = 0;
+        let mut line_start = true;
+
+        for token in &self.tokens {
+            match *token {
+                BrainfuckToken::Command { .. }
+                | BrainfuckToken::Teleport { .. }
+                | BrainfuckToken::Output
+                | BrainfuckToken::Input => {
+                    if line_start {
+                        if !res.is_empty() {
+                            res.push('\n');
+                        }
+
+                        res += &"  ".repeat(indent);
+                    }
+
+                    write!(res, "{}", token).unwrap();
+                    line_start = match *token {
+                        BrainfuckToken::Command { cmd, .. } => cmd.is_move(),
+                        _ => true,
+                    };
+                }
+
+                BrainfuckToken::JumpIfZero { .. } | BrainfuckToken::JumpIfNotMine { .. } => {
+                    if !res.is_empty() {
+                        res.push('\n');
+                    }
+
+                    res += &"  ".repeat(indent);
+                    write!(res, "{}", token).unwrap();
+                    indent += 1;
+                    line_start = true;
+                }
+
+                BrainfuckToken::JumpIfNonzero { .. } | BrainfuckToken::JumpIfMine { .. } => {
+                    indent -= 1;
+                    res.push('\n');
+                    res += &"  ".repeat(indent);
+                    write!(res, "{}", token).unwrap();
+                    line_start = true;
+                }
+            }
+        }
+
+        res
+    }
+
+    /// Returns the length of the program, counting every repetition of a
+    /// command separately.
+    pub fn len(&self) -> usize {
+        self.tokens.iter().map(BrainfuckToken::len).sum()
+    }
+
+    /// Returns whether the program is empty.
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Returns the number of commands in the program, not counting brackets.
+    pub fn len_no_loops(&self) -> usize {
+        self.tokens
+            .iter()
+            .filter(|t| {
+                matches!(
+                    t,
+                    BrainfuckToken::Command { .. }
+                        | BrainfuckToken::Teleport { .. }
+                        | BrainfuckToken::Output
+                        | BrainfuckToken::Input
+                )
+            })
+            .map(BrainfuckToken::len)
+            .sum()
+    }
+
+    /// Returns the number of loops in the program.
+    pub fn loop_count(&self) -> usize {
+        self.tokens
+            .iter()
+            .filter(|t| {
+                matches!(
+                    t,
+                    BrainfuckToken::JumpIfZero { .. } | BrainfuckToken::JumpIfNotMine { .. }
+                )
+            })
+            .count()
+    }
+
+    /// Reads the token at the current position.
+    fn read(&self) -> Option<BrainfuckToken> {
+        self.tokens.get(self.pointer).copied()
+    }
+
+    /// Returns whether the program has ended.
+    fn is_done(&self) -> bool {
+        self.pointer >= self.tokens.len()
+    }
+
+    /// Executes a single token on the given board, and moves the data pointer
+    /// accordingly. A repeated command costs one step per repetition, and
+    /// fails once it exceeds the remaining `steps`. Returns the number of
+    /// steps taken, or `None` if the program has ended.
+    pub fn step<M: Memory>(&mut self, memory: &mut M, steps: u32) -> EvalResult<Option<u32>> {
+        let instr = match self.read() {
+            Some(instr) => instr,
+            None => return Ok(None),
+        };
+
+        match instr {
+            BrainfuckToken::Command { cmd, count } => {
+                // Runs out of steps partway, after any earlier error.
+                let allowed = count.min(steps as usize);
+                memory.exec_n(cmd, allowed)?;
+                if allowed < count {
+                    return Err(EvalError::MaxSteps);
+                }
+
+                self.advance();
+                return Ok(Some(count as u32));
+            }
+
+            BrainfuckToken::JumpIfZero { target } => {
+                if memory.is_empty() {
+                    self.jump(target);
+                } else {
+                    self.advance();
+                }
+            }
+
+            BrainfuckToken::JumpIfNonzero { target } => {
+                if !memory.is_empty() {
+                    self.jump(target);
+                } else {
+                    self.advance();
+                }
+            }
+
+            BrainfuckToken::JumpIfNotMine { target } => {
+                if !memory.is_mine() {
+                    self.jump(target);
+                } else {
+                    self.advance();
+                }
+            }
+
+            BrainfuckToken::JumpIfMine { target } => {
+                if memory.is_mine() {
+                    self.jump(target);
+                } else {
+                    self.advance();
+                }
+            }
+
+            BrainfuckToken::Teleport { target } => {
+                memory.jump_to(target)?;
+                self.advance();
+            }
+
+            BrainfuckToken::Output => {
+                memory.write_output();
+                self.advance();
+            }
+
+            // Reading a value costs a single step, however large it is.
+            BrainfuckToken::Input => {
+                memory.read_input(self.input.pop_front())?;
+                self.advance();
+            }
+        }
+
+        Ok(Some(1))
+    }
+
+    /// Runs the program on the given memory for at most the specified amount
+    /// of steps, counting the tokens it executes into `profile` if given.
+    /// Returns the number of steps taken.
+    pub fn run<M: Memory>(
+        &mut self,
+        memory: &mut M,
+        steps: u32,
+        mut profile: Option<&mut MoveProfile>,
+    ) -> EvalResult<u32> {
+        let mut left = steps;
+        while left > 0 {
+            let token = self.read();
+            match self.step(memory, left)? {
+                Some(taken) => {
+                    left -= taken;
+                    if let (Some(profile), Some(token)) = (profile.as_deref_mut(), token) {
+                        profile.add(token, taken);
+                    }
+                }
+                None => break,
+            }
+        }
+
+        // The last step might have finished the program.
+        if self.is_done() {
+            Ok(steps - left)
+        } else {
+            Err(EvalError::MaxSteps)
+        }
+    }
+
+    /// Advances the data pointer.
+    fn advance(&mut self) {
+        self.pointer += 1;
+    }
+
+    /// Jumps the pointer to the target.
+    fn jump(&mut self, target: usize) {
+        self.pointer = target;
+    }
+}
+
+/// The condition that ends the game.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum WinRule {
+    /// The game ends once all but the buffer buckets are locked, and whoever
+    /// owns the most buckets wins.
+    #[default]
+    Standard,
+
+    /// The first player to own this many adjacent buckets wins immediately.
+    /// Otherwise, the game ends as in the standard rule.
+    Consecutive(usize),
+}
+
+impl Display for WinRule {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Self::Standard => write!(f, "standard"),
+            Self::Consecutive(k) => write!(f, "{} consecutive buckets", k),
+        }
+    }
+}
+
+/// How ties on the number of owned buckets are broken.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TieBreak {
+    /// Ties are left as they are.
+    #[default]
+    None,
+
+    /// The tied player with the most counters in unlocked buckets wins.
+    Counters,
+}
+
+impl Display for TieBreak {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Counters => write!(f, "counters"),
+        }
+    }
+}
+
+/// A limit on how many counters each player may remove.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum DecrementLimit {
+    /// At most this many decrements in each move.
+    PerMove(usize),
+
+    /// At most this many decrements over the whole game.
+    PerGame(usize),
+}
+
+impl DecrementLimit {
+    /// Returns the maximum number of decrements.
+    pub fn max(self) -> usize {
+        match self {
+            Self::PerMove(max) | Self::PerGame(max) => max,
+        }
+    }
+}
+
+impl Display for DecrementLimit {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Self::PerMove(max) => write!(f, "{} per move", max),
+            Self::PerGame(max) => write!(f, "{} per game", max),
+        }
+    }
+}
+
+/// How the buckets of the board are laid out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum BoardLayout {
+    /// The buckets form a line, or two with a second tape.
+    #[default]
+    Linear,
+
+    /// The buckets form a grid, filled row by row. `<` and `>` move along a
+    /// row, and `(` and `)` move up and down a column.
+    Grid {
+        /// The number of rows.
+        rows: usize,
+
+        /// The number of buckets in each row.
+        cols: usize,
+    },
+}
+
+impl Display for BoardLayout {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Self::Linear => write!(f, "linear"),
+            Self::Grid { rows, cols } => write!(f, "{}x{} grid", rows, cols),
+        }
+    }
+}
+
+/// How many times each kind of token was executed during a move.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MoveProfile {
+    /// The number of increments.
+    pub increments: u32,
+
+    /// The number of decrements.
+    pub decrements: u32,
+
+    /// The number of moves to the left.
+    pub moves_left: u32,
+
+    /// The number of moves to the right.
+    pub moves_right: u32,
+
+    /// The number of times the start of a loop was reached.
+    pub loop_starts: u32,
+
+    /// The number of times the end of a loop was reached.
+    pub loop_ends: u32,
+}
+
+impl MoveProfile {
+    /// Counts a token that was executed the given number of times.
+    fn add(&mut self, token: BrainfuckToken, times: u32) {
+        let count = match token {
+            BrainfuckToken::Command { cmd, .. } => match cmd {
+                Command::Increment => &mut self.increments,
+                Command::Decrement => &mut self.decrements,
+                Command::MoveLeft => &mut self.moves_left,
+                Command::MoveRight => &mut self.moves_right,
+                Command::Clear
+                | Command::Seek
+                | Command::SwitchTape
+                | Command::MoveUp
+                | Command::MoveDown => return,
+            },
+            BrainfuckToken::JumpIfZero { .. } | BrainfuckToken::JumpIfNotMine { .. } => {
+                &mut self.loop_starts
+            }
+            BrainfuckToken::JumpIfNonzero { .. } | BrainfuckToken::JumpIfMine { .. } => {
+                &mut self.loop_ends
+            }
+            BrainfuckToken::Teleport { .. } | BrainfuckToken::Output | BrainfuckToken::Input => {
+                return
+            }
+        };
+
+        *count += times;
+    }
+}
+
+impl Display for MoveProfile {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f,
+            "{} increments, {} decrements, {} moves, {} loop iterations",
+            self.increments,
+            self.decrements,
+            self.moves_left + self.moves_right,
+            self.loop_ends
+        )
+    }
+}
+
+/// Represents the memory Brainfuck runs on.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GameBoard {
+    /// The buckets, i.e. the different entries in the memory array.
+    pub buckets: Vec<Bucket>,
+
+    /// The index of the active bucket.
+    pub position: usize,
+
+    /// The turn number in the game.
+    pub turn: u32,
+
+    /// The player characters in the game, in cyclic order.
+    pub players: Players,
+
+    /// The number of buckets that can remain unfilled.
+    pub buffer_buckets: u16,
+
+    /// The counters that each bucket starts with, as pairs of bucket indices
+    /// and counters, from bottom to top.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub start: Vec<(usize, Vec<Player>)>,
+
+    /// The bonus added to the score of the last player in the turn order.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub komi: f64,
+
+    /// The number of upcoming moves of each player that are one character
+    /// shorter than usual.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub handicaps: Vec<usize>,
+
+    /// The number of counters each player may place over the whole game, or
+    /// `None` if it's unlimited.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub supply: Option<usize>,
+
+    /// The number of counters each player has left to place. Players past the
+    /// end of the list have their whole supply left.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub stock: Vec<usize>,
+
+    /// The limit on how many counters each player may remove, if any.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub decrement_limit: Option<DecrementLimit>,
+
+    /// The number of decrements each player has made, in the current move or
+    /// over the whole game depending on the limit.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub decrements: Vec<usize>,
+
+    /// Whether locking a bucket empties its unlocked neighbors.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub capture: bool,
+
+    /// The indices of the buckets emptied by captures during the last move.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub captured: Vec<usize>,
+
+    /// The rule that decides who owns a full bucket.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub lock_rule: LockRule,
+
+    /// How ties on the number of owned buckets are broken.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tie_break: TieBreak,
+
+    /// The condition that ends the game.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub win_rule: WinRule,
+
+    /// The indices of the buckets locked during the last move, followed by
+    /// those locked as they shrank afterwards.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub new_locks: Vec<usize>,
+
+    /// Whether a player may spend their move unlocking an opponent's bucket.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub unlock: bool,
+
+    /// Whether unlocked buckets lose a counter at the end of every round.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub decay: bool,
+
+    /// The indices of the buckets that lost a counter at the end of the last
+    /// round, if it just ended.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub decayed: Vec<usize>,
+
+    /// Whether counters fall into the bucket to their left at the end of
+    /// every move, while it has room.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub gravity: bool,
+
+    /// The indices of the buckets that counters fell into at the end of the
+    /// last move.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub settled: Vec<usize>,
+
+    /// The number of full rounds after which every unlocked bucket loses a
+    /// space, if buckets shrink.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub shrink: Option<usize>,
+
+    /// The number of spaces each bucket has lost, so that they can be
+    /// restored when the game is reset.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub shrinkage: Vec<usize>,
+
+    /// The indices of the buckets that lost a space at the end of the last
+    /// round, if it just ended.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub shrunk: Vec<usize>,
+
+    /// Whether a tied game continues until the next bucket is locked.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub overtime: bool,
+
+    /// The index of the turn whose move started overtime, if it has started.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub overtime_turn: Option<u32>,
+
+    /// The number of turns after which the game is adjudicated, if any.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub max_turns: Option<u32>,
+
+    /// The number of turns at the start of the game during which moves may
+    /// not contain loops.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub opening_no_brackets: u32,
+
+    /// The index of the first bucket of the second tape, if the game is
+    /// played on two. The buckets before it make up the first tape.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub second_tape: Option<usize>,
+
+    /// The position of the pointer on the tape that isn't active. This is
+    /// only meaningful when there's a second tape.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub other_position: usize,
+
+    /// How the buckets are laid out. A grid can't have a second tape.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub layout: BoardLayout,
+
+    /// The number of steps the last successful move took to run.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub steps_used_last_move: u32,
+
+    /// The contents of the buckets written by the last move with `.`, in
+    /// order.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub output: Vec<Vec<Player>>,
+
+    /// Whether moves keep count of the tokens they execute.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub profiling: bool,
+
+    /// The tokens executed by the last successful move, if it was profiled.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub last_move_profile: Option<MoveProfile>,
+}
+
+impl Memory for GameBoard {
+    /// Pointer moves are applied at once, while the other commands still go
+    /// counter by counter, so that locks and supplies behave as usual.
+    fn exec_n(&mut self, cmd: Command, count: usize) -> EvalResult<()> {
+        match cmd {
+            Command::MoveLeft => {
+                if count > self.position - self.row_range().start {
+                    Err(EvalError::UnderBounds)
+                } else {
+                    self.position -= count;
+                    Ok(())
+                }
+            }
+
+            Command::MoveRight => {
+                if count >= self.row_range().end - self.position {
+                    Err(EvalError::OverBounds)
+                } else {
+                    self.position += count;
+                    Ok(())
+                }
+            }
+
+            Command::MoveUp | Command::MoveDown => self.move_vertically(cmd, count),
+
+            _ => (0..count).try_for_each(|_| self.exec(cmd)),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.bucket().is_empty()
+    }
+
+    fn is_mine(&self) -> bool {
+        self.bucket().counters.last() == Some(&self.player())
+    }
+
+    fn jump_to(&mut self, idx: usize) -> EvalResult<()> {
+        self.teleport(idx)
+    }
+
+    fn write_output(&mut self) {
+        let counters = self.bucket().counters.clone();
+        self.output.push(counters);
+    }
+
+    /// Adds as many counters as the value, one at a time. Running out of
+    /// input is an error.
+    fn read_input(&mut self, value: Option<usize>) -> EvalResult<()> {
+        self.exec_n(Command::Increment, value.ok_or(EvalError::NoInput)?)
+    }
+}
+
+impl Display for GameBoard {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        self.fmt_with(f, DisplayMode::Plain)
+    }
+}
+
+impl GameBoard {
+    /// Returns a value that displays the board in the given mode.
+    pub fn display(&self, mode: DisplayMode) -> BoardView<'_> {
+        BoardView { board: self, mode }
+    }
+
+    /// Writes the board, drawing the counters in the given mode.
+    fn fmt_with(&self, f: &mut Formatter, mode: DisplayMode) -> FmtResult {
+        writeln!(
+            f,
+            "Turn {} -- {} to move",
+            self.turn + 1,
+            mode.counter(self.player(), &self.players)
+        )?;
+
+        // Buckets without a label show their number instead.
+        let labels: Vec<_> = self
+            .buckets
+            .iter()
+            .enumerate()
+            .map(|(idx, b)| b.label.clone().unwrap_or_else(|| (idx + 1).to_string()))
+            .collect();
+
+        // Pads the labels so that the buckets line up.
+        let width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+
+        for (idx, (bucket, label)) in self.buckets.iter().zip(&labels).enumerate() {
+            // Each tape is headed by its number, and whether it's active.
+            if let Some(split) = self.second_tape {
+                if idx == 0 || idx == split {
+                    let active = self.tape_range().start == idx;
+                    let number = if idx == 0 { 1 } else { 2 };
+                    writeln!(
+                        f,
+                        "Tape {}{}:",
+                        number,
+                        if active { " (active)" } else { "" }
+                    )?;
+                }
+            }
+
+            // Each row of a grid is headed by its number.
+            if let BoardLayout::Grid { cols, .. } = self.layout {
+                if idx % cols == 0 {
+                    writeln!(f, "Row {}:", idx / cols + 1)?;
+                }
+            }
+
+            let pointer = if idx == self.position
+                || self.second_tape.is_some() && idx == self.other_position
+            {
+                ">"
+            } else {
+                " "
+            };
+            let prefix = format!("[{}]{}", label, pointer);
+            write!(f, "{:<w$} ", prefix, w = width + 3)?;
+            bucket.fmt_rich(f, mode, &self.players)?;
+            writeln!(f)?;
+        }
+
+        if self.supply.is_some() {
+            let stocks: Vec<_> = self
+                .players
+                .iter()
+                .enumerate()
+                .map(|(idx, player)| format!("{} {}", player, self.stock(idx).unwrap()))
+                .collect();
+
+            writeln!(f, "Supply: {}", stocks.join(", "))?;
+        }
+
+        if let Some(left) = self.decrements_left() {
+            writeln!(f, "Decrements left: {}", left)?;
+        }
+
+        if self.turn < self.opening_no_brackets {
+            writeln!(
+                f,
+                "Loops are banned until turn {}.",
+                self.opening_no_brackets + 1
+            )?;
+        }
+
+        if !self.captured.is_empty() {
+            let captured: Vec<_> = self
+                .captured
+                .iter()
+                .map(|idx| (idx + 1).to_string())
+                .collect();
+            writeln!(f, "Captured buckets: {}", captured.join(", "))?;
+        }
+
+        if !self.decayed.is_empty() {
+            let decayed: Vec<_> = self
+                .decayed
+                .iter()
+                .map(|idx| (idx + 1).to_string())
+                .collect();
+            writeln!(f, "Decayed buckets: {}", decayed.join(", "))?;
+        }
+
+        if !self.shrunk.is_empty() {
+            let shrunk: Vec<_> = self
+                .shrunk
+                .iter()
+                .map(|idx| (idx + 1).to_string())
+                .collect();
+            writeln!(f, "Shrunk buckets: {}", shrunk.join(", "))?;
+        }
+
+        if !self.settled.is_empty() {
+            let settled: Vec<_> = self
+                .settled
+                .iter()
+                .map(|idx| (idx + 1).to_string())
+                .collect();
+            writeln!(f, "Counters fell into buckets: {}", settled.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for GameBoard {
+    fn default() -> Self {
+        Self::new_symmetric(
+            DEFAULT_BUCKET_COUNT,
+            DEFAULT_BUCKET_CAPACITY,
+            DEFAULT_BUFFER_BUCKETS,
+        )
+    }
+}
+
+/// Parses a single bucket, as written by the board, returning the bucket and
+/// whether the pointer is on it. The bucket isn't validated.
+fn parse_bucket_line(line: &str) -> Option<(BucketData, bool)> {
+    let mut tokens: Vec<_> = line.split_whitespace().collect();
+
+    // Reads the owner.
+    let mut owner = None;
+    if tokens.len() >= 2 && tokens[tokens.len() - 2] == "✓" {
+        let mut chars = tokens.pop()?.chars();
+        owner = Some(Player::new(chars.next()?).ok()?);
+        if chars.next().is_some() {
+            return None;
+        }
+
+        tokens.pop();
+    }
+
+    // Reads the fill and capacity.
+    let (fill, capacity) = tokens.pop()?.split_once('/')?;
+    let fill: usize = fill.parse().ok()?;
+    let capacity: usize = capacity.parse().ok()?;
+
+    // Reads the label and pointer.
+    let prefix = tokens.first()?.strip_prefix('[')?;
+    let (label, pointer) = match prefix.strip_suffix("]>") {
+        Some(label) => (label, true),
+        None => (prefix.strip_suffix(']')?, false),
+    };
+
+    // Reads the counters, followed by the free spaces.
+    let contents: Vec<_> = match tokens[1..] {
+        [] => Vec::new(),
+        [contents] => contents.chars().collect(),
+        _ => return None,
+    };
+
+    if fill > capacity || contents.len() != capacity || contents[fill..].iter().any(|&c| c != '_') {
+        return None;
+    }
+
+    let counters: Vec<_> = contents[..fill]
+        .iter()
+        .map(|&c| Player::new(c).ok())
+        .collect::<Option<_>>()?;
+
+    // Boards are read with the default lock rule, so a lock it wouldn't have
+    // produced must have been made by an edit.
+    let forced =
+        owner.is_some() && (fill != capacity || LockRule::default().owner(&counters) != owner);
+
+    let data = BucketData {
+        counters,
+        capacity,
+        locked: owner.is_some(),
+        owner,
+        forced,
+        label: Some(label.to_owned()),
+    };
+
+    Some((data, pointer))
+}
+
+impl FromStr for GameBoard {
+    type Err = BoardParseError;
+
+    /// Parses a board in the format it's displayed in. Only the turn, the
+    /// pointers, the tapes, the rows and the buckets are read, so every other
+    /// setting is left as the default. The players are assumed to be the first few of
+    /// [`DEFAULT_PLAYER_SYMBOLS`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().map(str::trim).filter(|l| !l.is_empty());
+
+        // Reads the turn and the player to move.
+        let header = lines.next().ok_or(BoardParseError::InvalidHeader)?;
+        let (turn, mover) = header
+            .strip_prefix("Turn ")
+            .and_then(|h| h.strip_suffix(" to move"))
+            .and_then(|h| h.split_once(" -- "))
+            .ok_or(BoardParseError::InvalidHeader)?;
+
+        let turn = match turn.parse::<u32>() {
+            Ok(turn) if turn > 0 => turn - 1,
+            _ => return Err(BoardParseError::InvalidHeader),
+        };
+
+        let mut mover_chars = mover.chars();
+        let mover = match (mover_chars.next(), mover_chars.next()) {
+            (Some(c), None) => Player::new(c).map_err(|_| BoardParseError::InvalidHeader)?,
+            _ => return Err(BoardParseError::InvalidHeader),
+        };
+
+        // Reads the buckets and tape headers, up to the first line that isn't
+        // either. Each tape has its own pointer.
+        let mut buckets = Vec::new();
+        let mut pointers = vec![None];
+        let mut second_tape = None;
+        let mut active = None;
+        let mut rows = Vec::new();
+
+        for line in lines
+            .take_while(|l| l.starts_with('[') || l.starts_with("Tape ") || l.starts_with("Row "))
+        {
+            // Row headers must come in order, and note where each row starts.
+            if let Some(header) = line.strip_prefix("Row ") {
+                if header.strip_suffix(':') != Some(&(rows.len() + 1).to_string()) {
+                    return Err(BoardParseError::InvalidRows);
+                }
+
+                rows.push(buckets.len());
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix("Tape ") {
+                let (number, is_active) = match header.strip_suffix(" (active):") {
+                    Some(number) => (number, true),
+                    None => (
+                        header
+                            .strip_suffix(':')
+                            .ok_or(BoardParseError::InvalidTapes)?,
+                        false,
+                    ),
+                };
+
+                match number {
+                    "1" if buckets.is_empty() && active.is_none() => {}
+                    "2" if !buckets.is_empty() && second_tape.is_none() => {
+                        second_tape = Some(buckets.len());
+                        pointers.push(None);
+                    }
+                    _ => return Err(BoardParseError::InvalidTapes),
+                }
+
+                if is_active {
+                    if active.is_some() {
+                        return Err(BoardParseError::InvalidTapes);
+                    }
+
+                    active = Some(pointers.len() - 1);
+                }
+
+                continue;
+            }
+
+            let idx = buckets.len();
+            let (data, pointer) =
+                parse_bucket_line(line).ok_or(BoardParseError::InvalidBucket { position: idx })?;
+
+            let bucket =
+                Bucket::try_from(data).map_err(|reason| BoardParseError::ImpossibleBucket {
+                    position: idx,
+                    reason,
+                })?;
+
+            if pointer {
+                let position = pointers.last_mut().unwrap();
+                if position.is_some() {
+                    return Err(BoardParseError::InvalidPointer);
+                }
+
+                *position = Some(idx);
+            }
+
+            buckets.push(bucket);
+        }
+
+        if buckets.is_empty() {
+            return Err(BoardParseError::NoBuckets);
+        }
+
+        let pointers: Vec<_> = pointers
+            .into_iter()
+            .collect::<Option<_>>()
+            .ok_or(BoardParseError::InvalidPointer)?;
+
+        let active = match (second_tape, active) {
+            (None, _) => 0,
+            (Some(split), Some(active)) if split < buckets.len() => active,
+            _ => return Err(BoardParseError::InvalidTapes),
+        };
+
+        // Rows must all be as long as the first one, which starts the board.
+        let layout = if rows.is_empty() {
+            BoardLayout::Linear
+        } else {
+            let cols = rows.get(1).copied().unwrap_or(buckets.len());
+            let regular = rows
+                .iter()
+                .enumerate()
+                .all(|(row, &start)| start == row * cols);
+            if cols == 0 || !regular || cols * rows.len() != buckets.len() || second_tape.is_some()
+            {
+                return Err(BoardParseError::InvalidRows);
+            }
+
+            BoardLayout::Grid {
+                rows: rows.len(),
+                cols,
+            }
+        };
+
+        // Finds the fewest default players that include every symbol on the
+        // board, and whose turn it is.
+        let needed = buckets
+            .iter()
+            .flat_map(|b| &b.counters)
+            .chain(std::iter::once(&mover))
+            .filter(|p| !p.is_neutral())
+            .map(|p| DEFAULT_PLAYER_SYMBOLS.iter().position(|&c| Player(c) == *p))
+            .try_fold(2, |n, idx| idx.map(|idx| n.max(idx + 1)))
+            .ok_or(BoardParseError::UnknownPlayers)?;
+
+        let players = (needed..=DEFAULT_PLAYER_SYMBOLS.len())
+            .map(|n| {
+                Players(
+                    DEFAULT_PLAYER_SYMBOLS[..n]
+                        .iter()
+                        .copied()
+                        .map(Player)
+                        .collect(),
+                )
+            })
+            .find(|players| players[players.idx(turn)] == mover)
+            .ok_or(BoardParseError::UnknownPlayers)?;
+
+        let mut board = Self::new(Vec::new(), 0, players);
+        board.buckets = buckets;
+        board.position = pointers[active];
+        board.second_tape = second_tape;
+        board.layout = layout;
+        if second_tape.is_some() {
+            board.other_position = pointers[1 - active];
+        }
+        board.turn = turn;
+
+        // Majority locks can't happen under the default rule.
+        if board
+            .iter()
+            .any(|b| b.owner.is_some() && LockRule::Uniform.owner(&b.counters) != b.owner)
+        {
+            board.lock_rule = LockRule::Majority;
+        }
+
+        Ok(board)
+    }
+}
+
+impl GameBoard {
+    /// Initializes a new game with the specified buckets and players, and the
+    /// default settings.
+    pub fn new(capacities: Vec<usize>, buffer_buckets: u16, players: Players) -> Self {
+        let mut buckets = Vec::new();
+
+        for (idx, c) in capacities.into_iter().enumerate() {
+            let mut bucket = Bucket::new(c);
+            bucket.label = Some((idx + 1).to_string());
+            buckets.push(bucket);
+        }
+
+        Self {
+            buckets,
+            position: 0,
+            turn: 0,
+            players,
+            buffer_buckets,
+            start: Vec::new(),
+            komi: 0.0,
+            handicaps: Vec::new(),
+            supply: None,
+            stock: Vec::new(),
+            decrement_limit: None,
+            decrements: Vec::new(),
+            capture: false,
+            captured: Vec::new(),
+            lock_rule: LockRule::Uniform,
+            tie_break: TieBreak::None,
+            win_rule: WinRule::Standard,
+            new_locks: Vec::new(),
+            unlock: false,
+            decay: false,
+            decayed: Vec::new(),
+            gravity: false,
+            settled: Vec::new(),
+            shrink: None,
+            shrinkage: Vec::new(),
+            shrunk: Vec::new(),
+            overtime: false,
+            overtime_turn: None,
+            max_turns: None,
+            opening_no_brackets: 0,
+            second_tape: None,
+            other_position: 0,
+            layout: BoardLayout::Linear,
+            steps_used_last_move: 0,
+            output: Vec::new(),
+            profiling: false,
+            last_move_profile: None,
+        }
+    }
+
+    /// Resets the game state to the starting position.
+    pub fn reset(&mut self) {
+        for (bucket, &shrinkage) in self.buckets.iter_mut().zip(&self.shrinkage) {
+            bucket.capacity += shrinkage;
+        }
+
+        for bucket in &mut self.buckets {
+            bucket.empty();
+        }
+
+        for (position, counters) in &self.start {
+            let bucket = &mut self.buckets[*position];
+            bucket.counters.extend(counters);
+            bucket.update_lock(self.lock_rule);
+        }
+
+        self.handicaps = Vec::new();
+        self.stock = Vec::new();
+        self.decrements = Vec::new();
+        self.captured = Vec::new();
+        self.new_locks = Vec::new();
+        self.decayed = Vec::new();
+        self.settled = Vec::new();
+        self.shrinkage = Vec::new();
+        self.shrunk = Vec::new();
+        self.overtime_turn = None;
+        self.steps_used_last_move = 0;
+        self.last_move_profile = None;
+        self.output = Vec::new();
+        self.position = 0;
+        self.other_position = self.second_tape.unwrap_or(0);
+        self.turn = 0;
+    }
+
+    /// Resets the game, using the new specified capacities for the first tape
+    /// but keeping everything else the same. Since the starting position
+    /// might not fit the new buckets, it's cleared.
+    pub fn reset_with(&mut self, capacities: Vec<usize>) {
+        let (_, second) = self.tape_capacities();
+        self.rebuild(capacities, Some(second));
+    }
+
+    /// Resets the game, using the new specified capacities for the second
+    /// tape, or removing it if `None` is passed. Everything else is kept the
+    /// same, except for the starting position, which is cleared.
+    pub fn set_second_tape(&mut self, capacities: Option<Vec<usize>>) {
+        let (first, _) = self.tape_capacities();
+        self.rebuild(first, capacities);
+    }
+
+    /// Returns the capacities the buckets on the first and second tapes start
+    /// the game with. The second list is empty if there's only one tape.
+    pub fn tape_capacities(&self) -> (Vec<usize>, Vec<usize>) {
+        let mut capacities: Vec<_> = self.buckets.iter().map(Bucket::capacity).collect();
+        for (capacity, shrinkage) in capacities.iter_mut().zip(&self.shrinkage) {
+            *capacity += shrinkage;
+        }
+
+        let second = capacities.split_off(self.second_tape.unwrap_or(self.buckets.len()));
+        (capacities, second)
+    }
+
+    /// Replaces the buckets of both tapes, numbering them in order, and
+    /// clears the starting position. An empty second tape is dropped.
+    fn rebuild(&mut self, first: Vec<usize>, second: Option<Vec<usize>>) {
+        let second = second.filter(|capacities| !capacities.is_empty());
+        self.second_tape = second.as_ref().map(|_| first.len());
+        self.buckets = Vec::new();
+
+        for (idx, c) in first
+            .into_iter()
+            .chain(second.into_iter().flatten())
+            .enumerate()
+        {
+            let mut bucket = Bucket::new(c);
+            bucket.label = Some((idx + 1).to_string());
+            self.buckets.push(bucket);
+        }
+
+        // A grid that no longer fits the buckets is dropped.
+        if self.set_layout(self.layout).is_err() {
+            self.layout = BoardLayout::Linear;
+        }
+
+        self.start = Vec::new();
+        self.shrinkage = Vec::new();
+        self.position = 0;
+        self.other_position = self.second_tape.unwrap_or(0);
+        self.turn = 0;
+    }
+
+    /// Initializes a new game with the default players and settings, and the
+    /// given number of buckets, all with the same capacity.
+    pub fn new_symmetric(bucket_count: usize, capacity: usize, buffer_buckets: u16) -> Self {
+        Self::new(
+            vec![capacity; bucket_count],
+            buffer_buckets,
+            Players::default(),
+        )
+    }
+
+    /// Replaces the players. This is meant for building a new board, as the
+    /// game isn't reset.
+    pub fn with_players(mut self, players: Players) -> Self {
+        self.players = players;
+        self
+    }
+
+    /// Replaces the number of buffer buckets. This is meant for building a
+    /// new board, as the game isn't reset.
+    pub fn with_buffer(mut self, buffer_buckets: u16) -> Self {
+        self.buffer_buckets = buffer_buckets;
+        self
+    }
+
+    /// Renames the buckets, in order. There must be one label per bucket.
+    pub fn set_labels(&mut self, labels: Vec<String>) -> Result<(), EditError> {
+        if labels.len() != self.buckets.len() {
+            return Err(EditError::LabelCount {
+                labels: labels.len(),
+                buckets: self.buckets.len(),
+            });
+        }
+
+        for (bucket, label) in self.buckets.iter_mut().zip(labels) {
+            bucket.label = Some(label);
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the players, and resets the game. Buckets that started with
+    /// counters of players no longer in the game start empty instead.
+    pub fn set_players(&mut self, players: Players) {
+        self.players = players;
+        let players = &self.players;
+        self.start.retain(|(_, counters)| {
+            counters
+                .iter()
+                .all(|&p| p.is_neutral() || players.contains(p))
+        });
+        self.reset();
+    }
+
+    /// Sets the counters each bucket starts with, and resets the game to the
+    /// new starting position.
+    pub fn set_start(&mut self, start: Vec<(usize, Vec<Player>)>) -> Result<(), EditError> {
+        let mut fills = vec![0; self.bucket_count()];
+
+        for (position, counters) in &start {
+            let position = *position;
+            let fill = fills
+                .get_mut(position)
+                .ok_or(EditError::NoBucket { position })?;
+
+            if let Some(&player) = counters
+                .iter()
+                .find(|&&p| !p.is_neutral() && !self.players.contains(p))
+            {
+                return Err(EditError::UnknownPlayer { player });
+            }
+
+            *fill += counters.len();
+            if *fill > self.buckets[position].capacity() {
+                return Err(EditError::Overflow { position });
+            }
+        }
+
+        self.start = start;
+        self.reset();
+        Ok(())
+    }
+
+    /// Returns a reference to the bucket that's being pointed at.
+    fn bucket(&self) -> &Bucket {
+        &self.buckets[self.position]
+    }
+
+    /// Returns a mutable reference to the bucket that's being pointed at.
+    fn bucket_mut(&mut self) -> &mut Bucket {
+        &mut self.buckets[self.position]
+    }
+
+    /// Returns the number of buckets.
+    pub fn bucket_count(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// Returns the indices of the buckets on the active tape.
+    pub fn tape_range(&self) -> Range<usize> {
+        match self.second_tape {
+            Some(split) if self.position >= split => split..self.buckets.len(),
+            Some(split) => 0..split,
+            None => 0..self.buckets.len(),
+        }
+    }
+
+    /// Returns whether two buckets are on the same tape.
+    fn same_tape(&self, a: usize, b: usize) -> bool {
+        self.second_tape
+            .is_none_or(|split| (a < split) == (b < split))
+    }
+
+    /// Returns the indices of the buckets the pointer moves along with `<`
+    /// and `>`: its row on a grid, and otherwise the active tape.
+    fn row_range(&self) -> Range<usize> {
+        match self.layout {
+            BoardLayout::Linear => self.tape_range(),
+            BoardLayout::Grid { cols, .. } => {
+                let start = self.position - self.position % cols;
+                start..start + cols
+            }
+        }
+    }
+
+    /// Returns whether two buckets are on the same row of the grid, or on
+    /// the same tape if the board isn't a grid.
+    fn same_row(&self, a: usize, b: usize) -> bool {
+        match self.layout {
+            BoardLayout::Linear => self.same_tape(a, b),
+            BoardLayout::Grid { cols, .. } => a / cols == b / cols,
+        }
+    }
+
+    /// Returns the indices of the buckets next to the given one: those beside
+    /// it on its row or tape, and those above and below it on a grid.
+    fn neighbors(&self, idx: usize) -> Vec<usize> {
+        let mut neighbors: Vec<_> = [idx.checked_sub(1), Some(idx + 1)]
+            .iter()
+            .flatten()
+            .copied()
+            .filter(|&n| n < self.buckets.len() && self.same_row(n, idx))
+            .collect();
+
+        if let BoardLayout::Grid { cols, .. } = self.layout {
+            neighbors.extend(idx.checked_sub(cols));
+            if idx + cols < self.buckets.len() {
+                neighbors.push(idx + cols);
+            }
+        }
+
+        neighbors
+    }
+
+    /// Lays out the buckets as a grid, or as a line. A grid must have as
+    /// many cells as there are buckets, and the board can't have a second
+    /// tape.
+    pub fn set_layout(&mut self, layout: BoardLayout) -> Result<(), EditError> {
+        if let BoardLayout::Grid { rows, cols } = layout {
+            if rows.checked_mul(cols) != Some(self.buckets.len()) || self.second_tape.is_some() {
+                return Err(EditError::InvalidGrid { rows, cols });
+            }
+        }
+
+        self.layout = layout;
+        Ok(())
+    }
+
+    /// Returns the number of buckets that can ever be locked. Buckets with no
+    /// capacity can't hold any counters, so they can't be.
+    pub fn lockable_buckets(&self) -> usize {
+        self.iter().filter(|b| b.capacity() != 0).count()
+    }
+
+    fn iter(&self) -> Iter<Bucket> {
+        self.buckets.iter()
+    }
+
+    /// Increments the current bucket.
+    fn incr(&mut self) -> EvalResult<()> {
+        let player = self.player();
+        let idx = self.player_idx();
+        let position = self.position;
+
+        let stock = self.stock(idx);
+        if stock == Some(0) {
+            return Err(EvalError::OutOfCounters { player });
+        }
+
+        let rule = self.lock_rule;
+        self.bucket_mut().push(player, position, rule)?;
+
+        if let Some(stock) = stock {
+            self.set_stock(idx, stock - 1);
+        }
+
+        if self.bucket().locked {
+            self.new_locks.push(position);
+
+            if self.capture {
+                self.capture_around(position);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Empties the unlocked buckets next to the given one.
+    fn capture_around(&mut self, position: usize) {
+        for idx in self.neighbors(position) {
+            let bucket = &mut self.buckets[idx];
+            if !bucket.locked && !bucket.is_empty() {
+                bucket.empty();
+                self.captured.push(idx);
+            }
+        }
+    }
+
+    /// Decrements the current bucket. Removing one of your own counters
+    /// returns it to your supply.
+    fn decr(&mut self) -> EvalResult<()> {
+        if let Some(limit) = self.decrement_limit {
+            if self.decrements_left() == Some(0) {
+                return Err(EvalError::DecrementLimit { limit });
+            }
+        }
+
+        let position = self.position;
+        let counter = self.bucket_mut().pop(position)?;
+        let idx = self.player_idx();
+
+        if self.decrement_limit.is_some() {
+            if self.decrements.len() <= idx {
+                self.decrements.resize(idx + 1, 0);
+            }
+
+            self.decrements[idx] += 1;
+        }
+
+        if counter == self.player() {
+            if let (Some(stock), Some(supply)) = (self.stock(idx), self.supply) {
+                self.set_stock(idx, (stock + 1).min(supply));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Empties the current bucket. Every counter removed counts as a
+    /// decrement, so it's subject to the decrement limit and returns your own
+    /// counters to your supply.
+    fn clear(&mut self) -> EvalResult<()> {
+        if self.bucket().locked {
+            return Err(EvalError::LockedDecr {
+                position: self.position,
+            });
+        }
+
+        while !self.bucket().is_empty() {
+            self.decr()?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of counters the current player may still remove, or
+    /// `None` if there's no limit.
+    pub fn decrements_left(&self) -> Option<usize> {
+        let limit = self.decrement_limit?;
+        let used = self.decrements.get(self.player_idx()).copied().unwrap_or(0);
+        Some(limit.max().saturating_sub(used))
+    }
+
+    /// Returns the number of counters the player at the given index has left
+    /// to place, or `None` if the supply is unlimited.
+    pub fn stock(&self, idx: usize) -> Option<usize> {
+        self.supply
+            .map(|supply| self.stock.get(idx).copied().unwrap_or(supply))
+    }
+
+    /// Sets the number of counters the player at the given index has left to
+    /// place. Does nothing if the supply is unlimited.
+    fn set_stock(&mut self, idx: usize, stock: usize) {
+        if let Some(supply) = self.supply {
+            if self.stock.len() <= idx {
+                self.stock.resize(idx + 1, supply);
+            }
+
+            self.stock[idx] = stock;
+        }
+    }
+
+    /// Moves the position to the left.
+    fn move_left(&mut self) -> EvalResult<()> {
+        if self.position == self.row_range().start {
+            Err(EvalError::UnderBounds)
+        } else {
+            self.position -= 1;
+            Ok(())
+        }
+    }
+
+    /// Moves the position to the given bucket, which must be on the active
+    /// tape.
+    pub fn teleport(&mut self, idx: usize) -> EvalResult<()> {
+        let range = self.tape_range();
+        if idx < range.start {
+            Err(EvalError::UnderBounds)
+        } else if idx >= range.end {
+            Err(EvalError::OverBounds)
+        } else {
+            self.position = idx;
+            Ok(())
+        }
+    }
+
+    /// Moves the position to the leftmost unlocked bucket on the active tape.
+    fn seek(&mut self) -> EvalResult<()> {
+        let idx = self.tape_range().find(|&idx| !self.buckets[idx].locked);
+        self.position = idx.ok_or(EvalError::AllLocked)?;
+        Ok(())
+    }
+
+    /// Makes the other tape active.
+    fn switch_tape(&mut self) -> EvalResult<()> {
+        if self.second_tape.is_none() {
+            return Err(EvalError::SingleTape);
+        }
+
+        std::mem::swap(&mut self.position, &mut self.other_position);
+        Ok(())
+    }
+
+    /// Moves the position to the right.
+    fn move_right(&mut self) -> EvalResult<()> {
+        if self.position + 1 == self.row_range().end {
+            Err(EvalError::OverBounds)
+        } else {
+            self.position += 1;
+            Ok(())
+        }
+    }
+
+    /// Moves the position up or down the given number of rows of the grid.
+    fn move_vertically(&mut self, cmd: Command, count: usize) -> EvalResult<()> {
+        let (rows, cols) = match self.layout {
+            BoardLayout::Grid { rows, cols } => (rows, cols),
+            BoardLayout::Linear => return Err(EvalError::NotGrid),
+        };
+
+        let row = self.position / cols;
+        if cmd == Command::MoveUp {
+            if count > row {
+                return Err(EvalError::UnderBounds);
+            }
+
+            self.position -= count * cols;
+        } else {
+            if count >= rows - row {
+                return Err(EvalError::OverBounds);
+            }
+
+            self.position += count * cols;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the index of the current player.
+    pub fn player_idx(&self) -> usize {
+        self.players.idx(self.turn)
+    }
+
+    /// Returns the current player.
+    pub fn player(&self) -> Player {
+        self.players[self.player_idx()]
+    }
+
+    /// Advances the turn number, applying decay if a round just ended.
+    fn next_turn(&mut self) {
+        self.turn += 1;
+        self.decayed = Vec::new();
+
+        if self.decay && self.player_idx() == 0 && self.winners().is_none() {
+            self.apply_decay();
+        }
+
+        self.shrunk = Vec::new();
+        let rounds = self.turn as usize / self.player_count();
+        if self.player_idx() == 0
+            && self.shrink.is_some_and(|n| rounds.is_multiple_of(n))
+            && self.winners().is_none()
+        {
+            self.apply_shrink();
+        }
+    }
+
+    /// Removes a space from every unlocked bucket that has room to spare,
+    /// locking those that become full.
+    fn apply_shrink(&mut self) {
+        self.shrinkage.resize(self.buckets.len(), 0);
+
+        for (idx, bucket) in self.buckets.iter_mut().enumerate() {
+            if !bucket.locked && bucket.shrink(self.lock_rule) {
+                self.shrinkage[idx] += 1;
+                self.shrunk.push(idx);
+
+                if bucket.locked {
+                    self.new_locks.push(idx);
+                }
+            }
+        }
+    }
+
+    /// Removes the topmost counter from every unlocked, non-empty bucket.
+    fn apply_decay(&mut self) {
+        for (idx, bucket) in self.buckets.iter_mut().enumerate() {
+            if !bucket.locked && !bucket.is_empty() {
+                bucket.counters.pop();
+                self.decayed.push(idx);
+            }
+        }
+    }
+
+    /// Moves the topmost counter of each bucket into the one to its left,
+    /// while that one has room, until no more counters can fall. Locked
+    /// buckets neither give nor receive counters, and counters don't fall
+    /// from one tape into the other.
+    fn apply_gravity(&mut self) {
+        let mut moved = true;
+
+        while moved {
+            moved = false;
+
+            for idx in 1..self.buckets.len() {
+                if !self.same_row(idx - 1, idx) {
+                    continue;
+                }
+
+                let rule = self.lock_rule;
+                let (left, right) = self.buckets.split_at_mut(idx);
+                let (to, from) = (&mut left[idx - 1], &mut right[0]);
+                if to.locked || from.locked || to.free() == 0 {
+                    continue;
+                }
+
+                let counter = match from.counters.pop() {
+                    Some(counter) => counter,
+                    None => continue,
+                };
+
+                to.counters.push(counter);
+                to.update_lock(rule);
+                let locked = to.locked;
+
+                moved = true;
+                if !self.settled.contains(&(idx - 1)) {
+                    self.settled.push(idx - 1);
+                }
+
+                if locked {
+                    self.new_locks.push(idx - 1);
+
+                    if self.capture {
+                        self.capture_around(idx - 1);
+                    }
+                }
+            }
+        }
+
+        self.settled.sort_unstable();
+    }
+
+    /// Exchanges the symbols of the first two players, and advances the turn
+    /// number without touching the board. This implements the swap rule.
+    pub fn swap_first_players(&mut self) {
+        self.players.swap(0, 1);
+        self.next_turn();
+    }
+
+    /// Executes the specified [`Command`].
+    fn exec(&mut self, cmd: Command) -> EvalResult<()> {
+        match cmd {
+            Command::Increment => self.incr(),
+            Command::Decrement => self.decr(),
+            Command::MoveLeft => self.move_left(),
+            Command::MoveRight => self.move_right(),
+            Command::Clear => self.clear(),
+            Command::Seek => self.seek(),
+            Command::SwitchTape => self.switch_tape(),
+            Command::MoveUp | Command::MoveDown => self.move_vertically(cmd, 1),
+        }
+    }
+
+    /// Tries each of the basic commands on a copy of the board, returning
+    /// whether the current player could play it as a move on its own.
+    pub fn legal_commands(&self) -> Vec<(Command, EvalResult<()>)> {
+        [
+            Command::Increment,
+            Command::Decrement,
+            Command::MoveLeft,
+            Command::MoveRight,
+        ]
+        .iter()
+        .map(|&cmd| (cmd, self.clone().exec(cmd)))
+        .collect()
+    }
+
+    /// Returns the indices of the buckets that a single counter from the
+    /// current player would lock for them.
+    pub fn near_locks(&self) -> Vec<usize> {
+        let player = self.player();
+
+        self.iter()
+            .enumerate()
+            .filter(|(_, b)| {
+                let mut counters = b.counters.clone();
+                counters.push(player);
+                !b.locked && b.free() == 1 && self.lock_rule.owner(&counters) == Some(player)
+            })
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    /// Returns the maximum length of a move at the current turn, taking the
+    /// current player's handicap into account.
+    pub fn max_len(&self) -> u32 {
+        let handicapped = self
+            .handicaps
+            .get(self.player_idx())
+            .is_some_and(|&h| h > 0);
+        (self.turn + 1).saturating_sub(handicapped as u32)
+    }
+
+    /// Makes the next `moves` moves of the player at the given index one
+    /// character shorter than usual.
+    pub fn set_handicap(&mut self, idx: usize, moves: usize) {
+        self.handicaps.resize(self.player_count(), 0);
+        self.handicaps[idx] = moves;
+    }
+
+    /// Runs a tokenized Brainfuck program of at most the given length for at
+    /// most the specified amount of steps.
+    fn run(&mut self, mut bf: Brainfuck, steps: u32, turn: u32) -> EvalResult<()> {
+        if bf.len() > turn as usize {
+            return Err(EvalError::Length {
+                len: bf.len(),
+                chars: bf.chars,
+                turn,
+            });
+        }
+
+        if self.turn < self.opening_no_brackets && bf.loop_count() != 0 {
+            return Err(EvalError::OpeningRestriction {
+                turn: self.opening_no_brackets,
+            });
+        }
+
+        let profiling = self.profiling;
+        let mut profile = MoveProfile::default();
+        self.steps_used_last_move = bf.run(self, steps, profiling.then_some(&mut profile))?;
+        self.last_move_profile = profiling.then_some(profile);
+        Ok(())
+    }
+
+    /// Evaluates a Brainfuck string, and runs it.
+    pub fn eval<S: AsRef<str>>(&mut self, str: S, steps: u32) -> EvalResult<()> {
+        self.eval_with(str, steps, &Dialect::default())
+    }
+
+    /// Evaluates a Brainfuck string written in the given dialect, and runs it.
+    pub fn eval_with<S: AsRef<str>>(
+        &mut self,
+        str: S,
+        steps: u32,
+        dialect: &Dialect,
+    ) -> EvalResult<()> {
+        let bf = Brainfuck::with_dialect(str, dialect)?;
+        let backup = self.clone();
+        self.captured = Vec::new();
+        self.new_locks = Vec::new();
+        self.output = Vec::new();
+        let res = self.run(bf, steps, self.max_len());
+
+        if res.is_err() {
+            *self = backup;
+        } else {
+            self.end_move();
+        }
+
+        #[cfg(any(debug_assertions, feature = "check"))]
+        assert_eq!(self.check_invariants(), Ok(()));
+        res
+    }
+
+    /// Evaluates and runs a sequence of Brainfuck strings, returning the
+    /// result of each one. Moves with invalid characters, which were probably
+    /// comments, are played as empty moves instead. Any other error stops the
+    /// sequence, and is the last result returned.
+    pub fn eval_all(&mut self, moves: &[&str], steps: u32) -> Vec<EvalResult<()>> {
+        self.eval_all_with(moves, steps, &Dialect::default())
+    }
+
+    /// Evaluates and runs a sequence of Brainfuck strings written in the given
+    /// dialect. See [`Self::eval_all`].
+    pub fn eval_all_with(
+        &mut self,
+        moves: &[&str],
+        steps: u32,
+        dialect: &Dialect,
+    ) -> Vec<EvalResult<()>> {
+        let mut results = Vec::new();
+
+        for str in moves {
+            let res = match self.eval_with(str, steps, dialect) {
+                Err(err @ EvalError::InvalidChar { .. }) => {
+                    self.eval_with("", steps, dialect).and(Err(err))
+                }
+                res => res,
+            };
+
+            let stop = !matches!(res, Ok(()) | Err(EvalError::InvalidChar { .. }));
+            results.push(res);
+            if stop {
+                break;
+            }
+        }
+
+        results
+    }
+
+    /// Formats the output of the last move, such as `3 (XXO), 0, 1 (X)`, or
+    /// returns `None` if it wrote nothing.
+    pub fn fmt_output(&self) -> Option<String> {
+        if self.output.is_empty() {
+            return None;
+        }
+
+        let values: Vec<_> = self
+            .output
+            .iter()
+            .map(|counters| {
+                if counters.is_empty() {
+                    "0".to_owned()
+                } else {
+                    let len = counters.len();
+                    let counters: String = counters.iter().map(Player::to_string).collect();
+                    format!("{} ({})", len, counters)
+                }
+            })
+            .collect();
+
+        Some(values.join(", "))
+    }
+
+    /// Checks the rules that any reachable board follows, returning a
+    /// description of the first one that's broken. This runs after every move
+    /// in debug builds, or with the `check` feature.
+    #[cfg(any(test, debug_assertions, feature = "check"))]
+    pub fn check_invariants(&self) -> Result<(), String> {
+        if self.position >= self.buckets.len() {
+            return Err(format!("pointer {} is out of bounds", self.position));
+        }
+
+        if let Some(split) = self.second_tape {
+            if split == 0 || split >= self.buckets.len() {
+                return Err(format!("second tape starts at invalid bucket {}", split));
+            }
+
+            if self.other_position >= self.buckets.len()
+                || self.same_tape(self.position, self.other_position)
+            {
+                return Err("both pointers are on the same tape".to_owned());
+            }
+        }
+
+        if let BoardLayout::Grid { rows, cols } = self.layout {
+            if rows * cols != self.buckets.len() || self.second_tape.is_some() {
+                return Err(format!(
+                    "the {}x{} grid doesn't fit the buckets",
+                    rows, cols
+                ));
+            }
+        }
+
+        for (idx, bucket) in self.buckets.iter().enumerate() {
+            if bucket.counters.len() > bucket.capacity() {
+                return Err(format!("bucket {} is overfilled", idx));
+            }
+
+            if bucket.locked != bucket.owner.is_some() {
+                return Err(format!(
+                    "bucket {} is locked but has no owner, or the reverse",
+                    idx
+                ));
+            }
+
+            if let Some(owner) = bucket.owner {
+                if !self.players.contains(owner) {
+                    return Err(format!("bucket {} is owned by a non-player", idx));
+                }
+            }
+
+            // Only edits may lock a bucket that isn't full, or give it to
+            // someone the lock rule wouldn't.
+            if bucket.forced {
+                if !bucket.locked {
+                    return Err(format!("bucket {} is forced but not locked", idx));
+                }
+            } else if bucket.locked
+                && (bucket.free() != 0 || self.lock_rule.owner(&bucket.counters) != bucket.owner)
+            {
+                return Err(format!(
+                    "bucket {} is locked, but isn't full or has the wrong owner",
+                    idx
+                ));
+            }
+
+            if let Some(c) = bucket
+                .counters
+                .iter()
+                .find(|&&c| !c.is_neutral() && !self.players.contains(c))
+            {
+                return Err(format!("bucket {} has a counter of {}", idx, c));
+            }
+        }
+
+        if let Some(supply) = self.supply {
+            if self.stock.iter().any(|&stock| stock > supply) {
+                return Err("a player has more counters than the supply".to_owned());
+            }
+        }
+
+        let player_count = self.player_count();
+        if self.stock.len() > player_count
+            || self.handicaps.len() > player_count
+            || self.decrements.len() > player_count
+        {
+            return Err("some player's counts belong to nobody".to_owned());
+        }
+
+        let max_decrements = self.decrement_limit.map_or(0, DecrementLimit::max);
+        if self.decrements.iter().any(|&used| used > max_decrements) {
+            return Err("a player has made more decrements than allowed".to_owned());
+        }
+
+        if let Some(turn) = self.overtime_turn {
+            if !self.overtime || turn > self.turn {
+                return Err(format!("overtime started on future turn {}", turn));
+            }
+        }
+
+        // Buckets lose at most a space every few rounds.
+        if let Some(rounds) = self.shrink {
+            let max_shrinkage = self.turn as usize / player_count / rounds.max(1);
+            if self.shrinkage.iter().any(|&n| n > max_shrinkage) {
+                return Err(format!(
+                    "a bucket shrank more than {} times by turn {}",
+                    max_shrinkage, self.turn
+                ));
+            }
+        }
+
+        if let Some(&idx) = self
+            .new_locks
+            .iter()
+            .chain(&self.captured)
+            .find(|&&idx| idx >= self.buckets.len())
+        {
+            return Err(format!("bucket {} of the last move doesn't exist", idx));
+        }
+
+        Ok(())
+    }
+
+    /// Plays a sequence of moves, which must follow the turn order. Returns
+    /// the board after each of them. If any move fails, the board is left as
+    /// it was, and the error is returned together with the player who made it.
+    pub fn apply_move_batch(
+        &mut self,
+        moves: &[(Player, &str)],
+        steps: u32,
+    ) -> Result<Vec<GameBoard>, (Player, EvalError)> {
+        let backup = self.clone();
+        let mut boards = Vec::new();
+
+        for &(player, str) in moves {
+            let expected = self.player();
+            let res = if player == expected {
+                self.eval(str, steps)
+            } else {
+                Err(EvalError::WrongPlayer { expected })
+            };
+
+            if let Err(err) = res {
+                *self = backup;
+                return Err((player, err));
+            }
+
+            boards.push(self.clone());
+        }
+
+        Ok(boards)
+    }
+
+    /// Spends the current move removing the lock from an opponent's bucket
+    /// and popping one of its counters.
+    pub fn unlock_bucket(&mut self, position: usize) -> EvalResult<()> {
+        let player = self.player();
+
+        match self.buckets.get_mut(position) {
+            Some(bucket) if bucket.owner.is_some() && bucket.owner != Some(player) => {
+                bucket.locked = false;
+                bucket.owner = None;
+                bucket.forced = false;
+                bucket.counters.pop();
+            }
+            _ => return Err(EvalError::InvalidUnlock { position }),
+        }
+
+        self.captured = Vec::new();
+        self.new_locks = Vec::new();
+        self.end_move();
+        Ok(())
+    }
+
+    /// Plays a single-token bonus move for the player who just moved, without
+    /// passing the turn.
+    pub fn eval_extra<S: AsRef<str>>(
+        &mut self,
+        str: S,
+        steps: u32,
+        dialect: &Dialect,
+    ) -> EvalResult<()> {
+        let bf = Brainfuck::with_dialect(str, dialect)?;
+        let backup = self.clone();
+
+        // Plays as the previous player.
+        self.turn -= 1;
+        self.captured = Vec::new();
+        self.new_locks = Vec::new();
+        self.output = Vec::new();
+        let res = self.run(bf, steps, 1);
+        if res.is_ok() {
+            self.settle_move();
+        }
+        self.turn += 1;
+
+        if res.is_err() {
+            *self = backup;
+        }
+
+        #[cfg(any(debug_assertions, feature = "check"))]
+        assert_eq!(self.check_invariants(), Ok(()));
+        res
+    }
+
+    /// Returns whether the last move locked a bucket next to another one
+    /// locked by the same player. Buckets that locked as they shrank weren't
+    /// locked by the move.
+    pub fn adjacent_lock(&self) -> bool {
+        let mut new_locks = self
+            .new_locks
+            .iter()
+            .filter(|idx| !self.shrunk.contains(idx));
+
+        // Neighbors locked by the same move weren't locked beforehand.
+        new_locks.any(|&idx| {
+            let owner = self.buckets[idx].owner;
+            self.neighbors(idx)
+                .into_iter()
+                .any(|n| self.buckets[n].owner == owner && !self.new_locks.contains(&n))
+        })
+    }
+
+    /// Does the bookkeeping after a successful move, and passes the turn.
+    fn end_move(&mut self) {
+        self.settle_move();
+        self.next_turn();
+    }
+
+    /// Does the bookkeeping after a successful move, including a bonus one:
+    /// counts down the player's handicap, lets counters fall, and starts
+    /// overtime if needed.
+    fn settle_move(&mut self) {
+        let idx = self.player_idx();
+        if let Some(handicap) = self.handicaps.get_mut(idx) {
+            *handicap = handicap.saturating_sub(1);
+        }
+
+        self.reset_move_decrements();
+
+        self.settled = Vec::new();
+        if self.gravity {
+            self.apply_gravity();
+        }
+
+        // Starts overtime if the game would otherwise end in a tie.
+        if self.overtime
+            && self.overtime_turn.is_none()
+            && self.winning_stretch().is_none()
+            && self.scored_winners().is_some_and(|w| w.is_draw())
+        {
+            self.overtime_turn = Some(self.turn);
+        }
+    }
+
+    /// Resets the current player's decrement count, if the limit is per move.
+    fn reset_move_decrements(&mut self) {
+        let idx = self.player_idx();
+
+        if let Some(DecrementLimit::PerMove(_)) = self.decrement_limit {
+            if let Some(decrements) = self.decrements.get_mut(idx) {
+                *decrements = 0;
+            }
+        }
+    }
+
+    /// Returns the number of players in the game.
+    pub fn player_count(&self) -> usize {
+        self.players.len()
+    }
+
+    /// Returns a mutable reference to the bucket at the given position, for
+    /// editing the board by hand.
+    fn edit_bucket(&mut self, position: usize) -> Result<&mut Bucket, EditError> {
+        self.buckets
+            .get_mut(position)
+            .ok_or(EditError::NoBucket { position })
+    }
+
+    /// Places counters from a player onto a bucket, regardless of whose turn it is.
+    pub fn edit_place(
+        &mut self,
+        position: usize,
+        player: Player,
+        count: usize,
+    ) -> Result<(), EditError> {
+        if !player.is_neutral() && !self.players.contains(player) {
+            return Err(EditError::UnknownPlayer { player });
+        }
+
+        let rule = self.lock_rule;
+        let bucket = self.edit_bucket(position)?;
+        if bucket.free() < count {
+            return Err(EditError::Overflow { position });
+        }
+
+        bucket.counters.resize(bucket.fill() + count, player);
+        bucket.update_lock(rule);
+        Ok(())
+    }
+
+    /// Removes the topmost counters from a bucket, even if it's locked.
+    pub fn edit_remove(&mut self, position: usize, count: usize) -> Result<(), EditError> {
+        let rule = self.lock_rule;
+        let bucket = self.edit_bucket(position)?;
+        if bucket.fill() < count {
+            return Err(EditError::Underflow { position });
+        }
+
+        let fill = bucket.fill();
+        bucket.counters.truncate(fill - count);
+        bucket.update_lock(rule);
+        Ok(())
+    }
+
+    /// Moves the pointer to the given bucket. If it's on the other tape, that
+    /// tape becomes the active one.
+    pub fn edit_pointer(&mut self, position: usize) -> Result<(), EditError> {
+        self.edit_bucket(position)?;
+        if !self.same_tape(self.position, position) {
+            self.other_position = self.position;
+        }
+
+        self.position = position;
+        Ok(())
+    }
+
+    /// Recomputes the lock state of a bucket, or locks it regardless of its
+    /// contents if `force` is set.
+    pub fn edit_lock(&mut self, position: usize, force: bool) -> Result<(), EditError> {
+        let rule = self.lock_rule;
+        let bucket = self.edit_bucket(position)?;
+
+        if force {
+            bucket.force_lock(position)
+        } else {
+            bucket.update_lock(rule);
+            Ok(())
+        }
+    }
+
+    /// Returns the number of locked buckets.
+    pub fn locked_buckets(&self) -> usize {
+        self.iter().filter(|b| b.locked).count()
+    }
+
+    /// Returns the number of buckets locked by the given player.
+    pub fn locked_by(&self, player: Player) -> usize {
+        self.iter().filter(|b| b.owner == Some(player)).count()
+    }
+
+    /// Returns the number of buckets holding counters from more than one
+    /// player. Neutral counters aren't taken into account.
+    pub fn contested_count(&self) -> usize {
+        self.iter()
+            .filter(|b| {
+                let mut counters = b.counters.iter().filter(|c| !c.is_neutral());
+                let first = counters.next();
+                counters.any(|c| Some(c) != first)
+            })
+            .count()
+    }
+
+    /// Returns the number of buckets that must be filled in order to win.
+    pub fn win_bucket_count(&self) -> u16 {
+        (self.lockable_buckets() as u16).saturating_sub(self.buffer_buckets)
+    }
+
+    /// Returns the winners of the game.
+    pub fn winners(&self) -> Option<Winners> {
+        self.regular_winners().or_else(|| {
+            if self.turn_limit_reached() {
+                Some(self.adjudicate())
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Returns whether the maximum number of turns has been played.
+    fn turn_limit_reached(&self) -> bool {
+        self.max_turns.is_some_and(|n| self.turn >= n)
+    }
+
+    /// Returns whether the game is over, and it was adjudicated because the
+    /// maximum number of turns was reached.
+    pub fn adjudicated(&self) -> bool {
+        self.turn_limit_reached() && self.regular_winners().is_none()
+    }
+
+    /// Ranks the players by the buckets they own, and then by the counters
+    /// they have on the board, regardless of whether the game is over.
+    pub fn adjudicate(&self) -> Winners {
+        self.break_ties(|p| self.counters(p))
+    }
+
+    /// Returns the winners of the game, if it ended by locking buckets.
+    fn regular_winners(&self) -> Option<Winners> {
+        if let Some((player, _)) = self.winning_stretch() {
+            return Some(std::iter::once(player).collect());
+        }
+
+        if let Some(turn) = self.overtime_turn {
+            // The first lock after overtime started wins outright.
+            if self.turn > turn + 1 {
+                if let Some(owner) = self
+                    .new_locks
+                    .first()
+                    .and_then(|&idx| self.buckets[idx].owner)
+                {
+                    return Some(std::iter::once(owner).collect());
+                }
+            }
+
+            if self.locked_buckets() < self.lockable_buckets() {
+                return None;
+            }
+        }
 
-        // Iterates over non-whitespace characters.
-        for (pos, c) in str.chars().filter(|c| !c.is_whitespace()).enumerate() {
-            match c {
-                '+' => {
-                    tokens.push(Command::Increment.into());
-                }
+        self.scored_winners()
+    }
 
-                '-' => {
-                    tokens.push(Command::Decrement.into());
-                }
+    /// Returns the winners by number of locked buckets, if enough of them
+    /// have been locked, ignoring overtime and the consecutive win rule.
+    fn scored_winners(&self) -> Option<Winners> {
+        if (self.locked_buckets() as u16) < self.win_bucket_count() {
+            return None;
+        }
 
-                '<' => {
-                    tokens.push(Command::MoveLeft.into());
-                }
+        Some(match self.tie_break {
+            TieBreak::None => self.top_scorers().into_iter().collect(),
+            TieBreak::Counters => self.break_ties(|p| self.unlocked_counters(p)),
+        })
+    }
 
-                '>' => {
-                    tokens.push(Command::MoveRight.into());
-                }
+    /// Returns the players tied for the greatest score, keeping only those
+    /// with the greatest value of `key` among them.
+    fn break_ties<F: Fn(Player) -> usize>(&self, key: F) -> Winners {
+        let top = self.top_scorers();
+        let max_key = top.iter().map(|&p| key(p)).max().unwrap_or(0);
 
-                '[' => {
-                    tokens.push(BrainfuckToken::JumpIfZero { target: 0 });
-                    queue.push_back(pos)
-                }
+        top.into_iter().filter(|&p| key(p) == max_key).collect()
+    }
 
-                ']' => {
-                    if let Some(target) = queue.pop_back() {
-                        tokens.push(BrainfuckToken::JumpIfNonzero { target });
+    /// Returns the player who owns enough adjacent buckets to win under the
+    /// consecutive win rule, together with the indices of the first such
+    /// stretch of buckets.
+    pub fn winning_stretch(&self) -> Option<(Player, Range<usize>)> {
+        let k = match self.win_rule {
+            WinRule::Consecutive(k) if k > 0 => k,
+            _ => return None,
+        };
 
-                        if let BrainfuckToken::JumpIfZero { target: old_target } =
-                            &mut tokens[target]
-                        {
-                            *old_target = pos;
-                        } else {
-                            unreachable!()
-                        }
-                    } else {
-                        return Err(EvalError::MismatchedRight { idx: pos });
+        let mut start = 0;
+        for (idx, bucket) in self.buckets.iter().enumerate() {
+            // A stretch can't carry over from one tape or row to the next.
+            if idx != 0 && !self.same_row(idx - 1, idx) {
+                start = idx;
+            }
+
+            match bucket.owner {
+                Some(owner) => {
+                    if self.buckets[start].owner != Some(owner) {
+                        start = idx;
                     }
-                }
 
-                _ => {
-                    return Err(EvalError::InvalidChar { c, idx: pos });
+                    if idx + 1 - start == k {
+                        return Some((owner, start..idx + 1));
+                    }
                 }
+                None => start = idx + 1,
             }
         }
 
-        if let Some(pos) = queue.pop_back() {
-            Err(EvalError::MismatchedLeft { idx: pos })
-        } else {
-            Ok(Self { tokens, pointer: 0 })
-        }
+        None
     }
 
-    /// Returns the length of the program.
-    fn len(&self) -> usize {
-        self.tokens.len()
+    /// Returns whether the last move started overtime.
+    pub fn overtime_started(&self) -> bool {
+        self.overtime_turn.is_some_and(|turn| turn + 1 == self.turn) && self.winners().is_none()
     }
 
-    /// Reads the token at the current position.
-    fn read(&self) -> Option<BrainfuckToken> {
-        self.tokens.get(self.pointer).copied()
-    }
+    /// Returns whether the game is over, and the tie-break decided who won.
+    pub fn won_by_tie_break(&self) -> bool {
+        if self.winning_stretch().is_some() || self.overtime_turn.is_some() || self.adjudicated() {
+            return false;
+        }
 
-    /// Advances the data pointer.
-    fn advance(&mut self) {
-        self.pointer += 1;
+        match self.winners() {
+            Some(winners) => winners.winner_count() < self.top_scorers().len(),
+            None => false,
+        }
     }
 
-    /// Jumps the pointer to the target.
-    fn jump(&mut self, target: usize) {
-        self.pointer = target;
+    /// Returns the players tied for the greatest score.
+    fn top_scorers(&self) -> Vec<Player> {
+        let scores = self.scores();
+        let max_score = scores
+            .iter()
+            .map(|&(_, score)| score)
+            .fold(f64::NEG_INFINITY, f64::max);
+
+        scores
+            .into_iter()
+            .filter(|&(_, score)| score == max_score)
+            .map(|(player, _)| player)
+            .collect()
     }
-}
 
-/// Represents the memory Brainfuck runs on.
-#[derive(Clone, Debug)]
-pub struct GameBoard {
-    /// The buckets, i.e. the different entries in the memory array.
-    pub buckets: Vec<Bucket>,
+    /// Returns the number of counters a player has on the board.
+    pub fn counters(&self, player: Player) -> usize {
+        self.iter()
+            .map(|b| b.counters.iter().filter(|&&c| c == player).count())
+            .sum()
+    }
 
-    /// The index of the active bucket.
-    pub position: usize,
+    /// Returns the number of counters a player has in unlocked buckets.
+    pub fn unlocked_counters(&self, player: Player) -> usize {
+        self.iter()
+            .filter(|b| !b.locked)
+            .map(|b| b.counters.iter().filter(|&&c| c == player).count())
+            .sum()
+    }
 
-    /// The turn number in the game.
-    pub turn: usize,
+    /// Returns the score of each player, i.e. the number of buckets they own,
+    /// plus the komi for the last player in the turn order.
+    pub fn scores(&self) -> Vec<(Player, f64)> {
+        let mut counts: HashMap<_, usize> = self.players.iter().map(|&p| (p, 0)).collect();
 
-    /// The player characters in the game, in cyclic order.
-    pub players: Players,
+        // Computes the number of buckets each player owns.
+        for owner in self.iter().filter_map(|b| b.owner) {
+            *counts.entry(owner).or_insert(0) += 1;
+        }
 
-    /// The number of buckets that can remain unfilled.
-    pub buffer_buckets: u16,
+        let last = self.player_count() - 1;
+        self.players
+            .iter()
+            .enumerate()
+            .map(|(idx, player)| {
+                let komi = if idx == last { self.komi } else { 0.0 };
+                (*player, counts[player] as f64 + komi)
+            })
+            .collect()
+    }
 }
 
-impl Display for GameBoard {
-    fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        writeln!(f, "Turn {} -- {} to move", self.turn + 1, self.player())?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    const X: Player = Player('X');
+    const O: Player = Player('O');
+
+    /// Checks that each command reported by [`GameBoard::legal_commands`]
+    /// succeeds as a move exactly when it's reported as legal.
+    fn assert_legal_matches_eval(board: &GameBoard) {
+        for (cmd, legal) in board.legal_commands() {
+            let res = board.clone().eval(cmd.to_string(), 100);
+            assert_eq!(legal, res, "{} at bucket {}", cmd, board.position);
+        }
+    }
 
-        for (idx, bucket) in self.buckets.iter().enumerate() {
-            if idx == self.position {
-                write!(f, "> ")?;
-            } else {
-                write!(f, "  ")?;
-            }
+    #[test]
+    fn legal_commands_match_eval_at_tape_edges() {
+        let mut board = GameBoard::new_symmetric(3, 2, 0);
+        board.set_second_tape(Some(vec![2, 2]));
 
-            writeln!(f, "{}", bucket)?;
+        for position in 0..board.bucket_count() {
+            board.position = position;
+            board.other_position = if position < 3 { 3 } else { 0 };
+            assert_legal_matches_eval(&board);
         }
 
-        Ok(())
+        board.position = 2;
+        board.other_position = 3;
+        assert_eq!(board.clone().eval(">", 100), Err(EvalError::OverBounds));
+        board.position = 3;
+        board.other_position = 2;
+        assert_eq!(board.clone().eval("<", 100), Err(EvalError::UnderBounds));
     }
-}
 
-impl Default for GameBoard {
-    fn default() -> Self {
-        Self::new(vec![10; 5], 0)
+    /// Returns a board with four buckets laid out as a 2x2 grid.
+    fn grid_board() -> GameBoard {
+        let mut board = GameBoard::new_symmetric(4, 2, 0);
+        board
+            .set_layout(BoardLayout::Grid { rows: 2, cols: 2 })
+            .unwrap();
+        board
     }
-}
 
-impl GameBoard {
-    /// Initializes a new game with the specified buckets and the default settings.
-    pub fn new(capacities: Vec<usize>, buffer_buckets: u16) -> Self {
-        let mut buckets = Vec::new();
+    #[test]
+    fn legal_commands_match_eval_on_grid() {
+        let mut board = grid_board();
 
-        for c in capacities {
-            buckets.push(Bucket::new(c));
+        for position in 0..board.bucket_count() {
+            board.position = position;
+            assert_legal_matches_eval(&board);
         }
+    }
 
-        Self {
-            buckets,
-            position: 0,
-            turn: 0,
-            players: Default::default(),
-            buffer_buckets,
+    #[test]
+    fn horizontal_moves_stay_in_row() {
+        let mut board = grid_board();
+        board.turn = 4;
+
+        for &position in &[1, 3] {
+            board.position = position;
+            assert_eq!(board.clone().eval(">", 100), Err(EvalError::OverBounds));
         }
-    }
 
-    /// Resets the game state.
-    pub fn reset(&mut self) {
-        for bucket in &mut self.buckets {
-            bucket.empty();
+        for &position in &[0, 2] {
+            board.position = position;
+            assert_eq!(board.clone().eval("<", 100), Err(EvalError::UnderBounds));
         }
 
-        self.position = 0;
-        self.turn = 0;
+        board.position = 2;
+        board.eval(">", 100).unwrap();
+        assert_eq!(board.position, 3);
     }
 
-    /// Resets the game, using the new specified capacities but keeping
-    /// everything else the same.
-    pub fn reset_with(&mut self, capacities: Vec<usize>) {
-        self.buckets = Vec::new();
+    #[test]
+    fn vertical_moves_stop_at_edges() {
+        let dialect = Dialect {
+            extensions: true,
+            ..Dialect::default()
+        };
+        let mut board = grid_board();
+
+        for &position in &[0, 1] {
+            board.position = position;
+            let res = board.clone().eval_with("(", 100, &dialect);
+            assert_eq!(res, Err(EvalError::UnderBounds));
+        }
 
-        for c in capacities {
-            self.buckets.push(Bucket::new(c));
+        for &position in &[2, 3] {
+            board.position = position;
+            let res = board.clone().eval_with(")", 100, &dialect);
+            assert_eq!(res, Err(EvalError::OverBounds));
         }
 
-        self.position = 0;
-        self.turn = 0;
+        board.position = 1;
+        board.eval_with(")", 100, &dialect).unwrap();
+        assert_eq!(board.position, 3);
+        assert_eq!(
+            GameBoard::default().eval_with(")", 100, &dialect),
+            Err(EvalError::NotGrid)
+        );
     }
 
-    /// Returns a reference to the bucket that's being pointed at.
-    fn bucket(&self) -> &Bucket {
-        &self.buckets[self.position]
-    }
+    #[test]
+    fn grid_display_groups_rows() {
+        let board = grid_board();
+        let display = board.to_string();
+        let lines: Vec<_> = display.lines().collect();
 
-    /// Returns a mutable reference to the bucket that's being pointed at.
-    fn bucket_mut(&mut self) -> &mut Bucket {
-        &mut self.buckets[self.position]
+        assert_eq!(lines.len(), 7);
+        assert_eq!(lines[1], "Row 1:");
+        assert_eq!(lines[4], "Row 2:");
+        assert_eq!(display.parse::<GameBoard>().unwrap(), board);
     }
 
-    /// Returns the number of buckets.
-    fn bucket_count(&self) -> usize {
-        self.buckets.len()
+    #[test]
+    fn set_layout_rejects_mismatched_grid() {
+        let mut board = GameBoard::new_symmetric(4, 2, 0);
+
+        assert!(matches!(
+            board.set_layout(BoardLayout::Grid { rows: 3, cols: 2 }),
+            Err(EditError::InvalidGrid { rows: 3, cols: 2 })
+        ));
+        assert!(matches!(
+            board.set_layout(BoardLayout::Grid { rows: 1, cols: 3 }),
+            Err(EditError::InvalidGrid { rows: 1, cols: 3 })
+        ));
+        assert_eq!(board.layout, BoardLayout::Linear);
+
+        board.set_second_tape(Some(vec![2, 2]));
+        board.reset_with(vec![2, 2]);
+        assert!(board
+            .set_layout(BoardLayout::Grid { rows: 2, cols: 2 })
+            .is_err());
     }
 
-    fn iter(&self) -> Iter<Bucket> {
-        self.buckets.iter()
+    /// Returns the players with the given symbols.
+    fn players(symbols: &str) -> Players {
+        let players = symbols.chars().map(|c| Player::new(c).unwrap()).collect();
+        Players::new(players).unwrap()
     }
 
-    /// Increments the current bucket.
-    fn incr(&mut self) -> EvalResult<()> {
-        let player = self.player();
-        let position = self.position;
-        self.bucket_mut().push(player, position)
+    #[test]
+    fn set_players_drops_stale_start() {
+        let y = Player::new('Y').unwrap();
+        let mut board = GameBoard::new_symmetric(3, 2, 0).with_players(players("XOY"));
+        board
+            .set_start(vec![(0, vec![y, y]), (1, vec![X])])
+            .unwrap();
+        assert!(board.buckets[0].locked);
+
+        board.set_players(players("XO"));
+        assert_eq!(board.start, vec![(1, vec![X])]);
+        assert!(board.buckets[0].is_empty());
+        assert_eq!(board.check_invariants(), Ok(()));
+
+        board.eval("+", 100).unwrap();
+        assert_eq!(board.buckets[0].counters, vec![X]);
+        assert_eq!(board.check_invariants(), Ok(()));
     }
 
-    /// Decrements the current bucket.
-    fn decr(&mut self) -> EvalResult<()> {
-        let position = self.position;
-        self.bucket_mut().pop(position)
-    }
+    #[test]
+    fn buckets_are_numbered_from_one() {
+        let mut board = GameBoard::new_symmetric(3, 2, 0);
+        let labels: Vec<_> = board.iter().map(|b| b.label.clone().unwrap()).collect();
+        assert_eq!(labels, ["1", "2", "3"]);
 
-    /// Moves the position to the left.
-    fn move_left(&mut self) -> EvalResult<()> {
-        if self.position == 0 {
-            Err(EvalError::UnderBounds)
-        } else {
-            self.position -= 1;
-            Ok(())
+        board.reset_with(vec![2, 2]);
+        board.set_second_tape(Some(vec![2]));
+        let labels: Vec<_> = board.iter().map(|b| b.label.clone().unwrap()).collect();
+        assert_eq!(labels, ["1", "2", "3"]);
+
+        for bucket in &mut board.buckets {
+            bucket.label = None;
         }
+        assert!(board.to_string().contains("[1]> __ 0/2"));
     }
 
-    /// Moves the position to the right.
-    fn move_right(&mut self) -> EvalResult<()> {
-        self.position += 1;
-        if self.position == self.buckets.len() {
+    #[test]
+    fn teleport_counts_from_one() {
+        let dialect = Dialect {
+            extensions: true,
+            ..Dialect::default()
+        };
+        let mut board = GameBoard::new_symmetric(3, 2, 0);
+        board.turn = 4;
+
+        let mut moved = board.clone();
+        moved.eval_with("@3", 100, &dialect).unwrap();
+        assert_eq!(moved.position, 2);
+        moved.eval_with("@1", 100, &dialect).unwrap();
+        assert_eq!(moved.position, 0);
+
+        assert_eq!(
+            board.eval_with("@4", 100, &dialect),
             Err(EvalError::OverBounds)
-        } else {
-            Ok(())
+        );
+        assert_eq!(
+            board.eval_with("+@0", 100, &dialect),
+            Err(EvalError::InvalidChar { c: '@', idx: 1 })
+        );
+
+        let bf = Brainfuck::with_dialect("@1@10", &dialect).unwrap();
+        assert_eq!(
+            bf.tokens(),
+            [
+                BrainfuckToken::Teleport { target: 0 },
+                BrainfuckToken::Teleport { target: 9 }
+            ]
+        );
+        assert_eq!(bf.pretty_print(), "@1\n@10");
+    }
+
+    /// Generates small boards with a random mix of rules.
+    fn arb_board() -> impl Strategy<Value = GameBoard> {
+        (
+            vec(0..4usize, 1..7),
+            0..3u16,
+            2..5usize,
+            any::<(bool, bool, bool, bool)>(),
+            proptest::option::of(0..8usize),
+            0..12u32,
+        )
+            .prop_map(|(capacities, buffer, player_count, rules, supply, turn)| {
+                let (capture, gravity, decay, majority) = rules;
+                let mut board =
+                    GameBoard::new(capacities, buffer, players(&"XOYZ"[..player_count]));
+                board.capture = capture;
+                board.gravity = gravity;
+                board.decay = decay;
+                board.supply = supply;
+                if majority {
+                    board.lock_rule = LockRule::Majority;
+                }
+
+                // Lays out a square number of buckets as a grid.
+                if board.bucket_count() == 4 {
+                    board
+                        .set_layout(BoardLayout::Grid { rows: 2, cols: 2 })
+                        .unwrap();
+                }
+
+                board.turn = turn;
+                board
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn moves_keep_invariants(
+            mut board in arb_board(),
+            moves in vec("[-+<>()~^@1-7\\[\\]{}]{0,16}", 1..10),
+        ) {
+            let dialect = Dialect {
+                extensions: true,
+                ..Dialect::default()
+            };
+
+            for program in moves {
+                let before = board.clone();
+                let res = board.eval_with(&program, 200, &dialect);
+                prop_assert_eq!(board.check_invariants(), Ok(()));
+
+                if res.is_err() {
+                    prop_assert_eq!(&board, &before);
+                    prop_assert_eq!(format!("{:?}", board), format!("{:?}", before));
+                }
+            }
         }
     }
 
-    /// Returns the index of the current player.
-    pub fn player_idx(&self) -> usize {
-        self.players.idx(self.turn)
+    /// A failing move: how to set up the board, the program, its dialect and
+    /// the error it fails with.
+    type FailedMove<'a> = (fn(&mut GameBoard), &'a str, &'a Dialect, EvalError);
+
+    /// Runs a move that must fail with the given error, and checks that the
+    /// board is left exactly as it was.
+    fn assert_rollback(board: &GameBoard, program: &str, dialect: &Dialect, err: EvalError) {
+        let mut after = board.clone();
+        assert_eq!(
+            after.eval_with(program, 100, dialect),
+            Err(err),
+            "{}",
+            program
+        );
+        assert_eq!(&after, board, "{}", program);
+        assert_eq!(
+            format!("{:?}", after),
+            format!("{:?}", board),
+            "{}",
+            program
+        );
     }
 
-    /// Returns the current player.
-    pub fn player(&self) -> Player {
-        self.players[self.player_idx()]
+    #[test]
+    fn failed_moves_roll_back() {
+        let standard = Dialect::default();
+        let extended = Dialect {
+            extensions: true,
+            io: true,
+            ..Dialect::default()
+        };
+
+        // Each case sets up a board, and plays a move that changes it before
+        // failing.
+        let cases: Vec<FailedMove> = vec![
+            (
+                |b| b.set_start(vec![(1, vec![X, O])]).unwrap(),
+                "+>+",
+                &standard,
+                EvalError::Overflow { position: 1 },
+            ),
+            (
+                |_| (),
+                "+>-",
+                &standard,
+                EvalError::Underflow { position: 1 },
+            ),
+            (|_| (), "+>>>", &standard, EvalError::OverBounds),
+            (|_| (), "+<", &standard, EvalError::UnderBounds),
+            (
+                |b| b.set_start(vec![(1, vec![X, X])]).unwrap(),
+                "+>+",
+                &standard,
+                EvalError::LockedIncr { position: 1 },
+            ),
+            (
+                |b| b.set_start(vec![(1, vec![X, X])]).unwrap(),
+                "+>-",
+                &standard,
+                EvalError::LockedDecr { position: 1 },
+            ),
+            (
+                |_| (),
+                "+[",
+                &standard,
+                EvalError::MismatchedLeft { idx: 1 },
+            ),
+            (
+                |_| (),
+                "+]",
+                &standard,
+                EvalError::MismatchedRight { idx: 1 },
+            ),
+            (|_| (), "+[><]", &standard, EvalError::MaxSteps),
+            (
+                |_| (),
+                "+ + + + + + + + + +",
+                &standard,
+                EvalError::Length {
+                    len: 10,
+                    chars: 19,
+                    turn: 9,
+                },
+            ),
+            (
+                |_| (),
+                "+[+}",
+                &extended,
+                EvalError::MixedBrackets { left: 1, right: 3 },
+            ),
+            (|_| (), "+[]", &standard, EvalError::TrivialLoop { idx: 1 }),
+            (
+                |b| b.supply = Some(1),
+                "+>+",
+                &standard,
+                EvalError::OutOfCounters { player: X },
+            ),
+            (
+                |b| b.set_start(vec![(0, vec![X, X]), (1, vec![O, O])]).unwrap(),
+                ">>++^",
+                &extended,
+                EvalError::AllLocked,
+            ),
+            (|_| (), "+%", &extended, EvalError::SingleTape),
+            (|_| (), "+(", &extended, EvalError::NotGrid),
+            (
+                |b| b.decrement_limit = Some(DecrementLimit::PerMove(1)),
+                "+-+-",
+                &standard,
+                EvalError::DecrementLimit {
+                    limit: DecrementLimit::PerMove(1),
+                },
+            ),
+            (
+                |b| {
+                    b.set_start(vec![(1, vec![X, O])]).unwrap();
+                    b.decrement_limit = Some(DecrementLimit::PerMove(1));
+                },
+                "+>~",
+                &extended,
+                EvalError::DecrementLimit {
+                    limit: DecrementLimit::PerMove(1),
+                },
+            ),
+            (
+                |b| b.opening_no_brackets = 20,
+                "+[-]",
+                &standard,
+                EvalError::OpeningRestriction { turn: 20 },
+            ),
+            (|_| (), "+,", &extended, EvalError::NoInput),
+            (
+                |_| (),
+                "+x",
+                &standard,
+                EvalError::InvalidChar { c: 'x', idx: 1 },
+            ),
+        ];
+
+        for (setup, program, dialect, err) in cases {
+            let mut board = GameBoard::new_symmetric(3, 2, 0);
+            setup(&mut board);
+            board.turn = 8;
+            assert_rollback(&board, program, dialect, err);
+        }
+
+        let shallow = Dialect {
+            max_nesting: 1,
+            ..Dialect::default()
+        };
+        let mut board = GameBoard::new_symmetric(3, 2, 0);
+        board.turn = 8;
+        assert_rollback(
+            &board,
+            "+[[+]]",
+            &shallow,
+            EvalError::TooDeep { idx: 2, depth: 1 },
+        );
     }
 
-    /// Advances the turn number.
-    fn next_turn(&mut self) {
-        self.turn += 1;
+    #[test]
+    fn error_indices_count_raw_chars() {
+        let cases = [
+            ("+\t+\nx", EvalError::InvalidChar { c: 'x', idx: 4 }),
+            ("++ é", EvalError::InvalidChar { c: 'é', idx: 3 }),
+            ("é", EvalError::InvalidChar { c: 'é', idx: 0 }),
+            ("\u{3000}+\u{a0}]", EvalError::MismatchedRight { idx: 3 }),
+            ("+ [\r\n+", EvalError::MismatchedLeft { idx: 2 }),
+            ("\u{2003}\u{2003}[[+]", EvalError::MismatchedLeft { idx: 2 }),
+        ];
+
+        for (program, err) in cases.iter() {
+            assert_eq!(Brainfuck::new(program).err(), Some(*err), "{:?}", program);
+        }
     }
 
-    /// Executes the specified [`Command`].
-    fn exec(&mut self, cmd: Command) -> EvalResult<()> {
-        match cmd {
-            Command::Increment => self.incr(),
-            Command::Decrement => self.decr(),
-            Command::MoveLeft => self.move_left(),
-            Command::MoveRight => self.move_right(),
+    #[test]
+    fn turns_cycle_through_every_player() {
+        for &symbols in &["XOY", "XOYZW"] {
+            let mut board = GameBoard::new_symmetric(3, 2, 0).with_players(players(symbols));
+
+            for turn in 0..3 * symbols.len() {
+                let expected = symbols.chars().nth(turn % symbols.len()).unwrap();
+                assert_eq!(board.player(), Player::new(expected).unwrap());
+                board.eval("", 100).unwrap();
+            }
         }
     }
 
-    /// Runs a tokenized Brainfuck program for at most the specified amount of steps.
-    fn run(&mut self, mut bf: Brainfuck, steps: u32) -> EvalResult<()> {
-        let turn = self.turn + 1;
+    #[test]
+    fn three_player_game() {
+        let y = Player::new('Y').unwrap();
+        let mut board = GameBoard::new(vec![1; 4], 0, players("XOY"));
 
-        if bf.len() > turn {
-            return Err(EvalError::Length {
-                len: bf.len(),
-                turn,
-            });
+        for (idx, program) in ["+", ">+", ">+"].iter().enumerate() {
+            board.eval(program, 100).unwrap();
+            assert_eq!(board.locked_buckets(), idx + 1);
+            assert!(board.winners().is_none());
         }
 
-        for _ in 0..steps {
-            if let Some(instr) = bf.read() {
-                match instr {
-                    BrainfuckToken::Command { cmd } => {
-                        self.exec(cmd)?;
-                        bf.advance();
-                    }
+        assert_eq!(board.buckets[2].owner, Some(y));
+        board.eval(">+", 100).unwrap();
+        assert_eq!(board.winners().unwrap().sole_winner(), Some(X));
+    }
 
-                    BrainfuckToken::JumpIfZero { target } => {
-                        if self.bucket().is_empty() {
-                            bf.jump(target);
-                        } else {
-                            bf.advance();
-                        }
-                    }
+    #[test]
+    fn three_way_tie() {
+        let mut board = GameBoard::new(vec![1; 3], 0, players("XOY"));
+        assert_eq!(
+            board.eval_all(&["+", ">+", ">+"], 100),
+            vec![Ok(()), Ok(()), Ok(())]
+        );
+
+        let winners = board.winners().unwrap();
+        assert!(winners.is_draw());
+        assert_eq!(winners.winner_count(), 3);
+        assert_eq!(winners.sole_winner(), None);
+    }
 
-                    BrainfuckToken::JumpIfNonzero { target } => {
-                        if !self.bucket().is_empty() {
-                            bf.jump(target);
-                        } else {
-                            bf.advance();
-                        }
-                    }
+    /// Returns a program with the given number of nested loops.
+    fn nested(depth: usize) -> String {
+        format!("{}+{}", "[".repeat(depth), "]".repeat(depth))
+    }
+
+    #[test]
+    fn nesting_limit() {
+        for &max_nesting in &[1, 3, Dialect::MAX_NESTING] {
+            let dialect = Dialect {
+                max_nesting,
+                ..Dialect::default()
+            };
+
+            assert!(Brainfuck::with_dialect(nested(max_nesting), &dialect).is_ok());
+            let err = Brainfuck::with_dialect(nested(max_nesting + 1), &dialect).err();
+            assert_eq!(
+                err,
+                Some(EvalError::TooDeep {
+                    idx: max_nesting,
+                    depth: max_nesting
+                })
+            );
+            assert!(err.unwrap().to_string().contains(&max_nesting.to_string()));
+        }
+    }
+
+    #[test]
+    fn zero_nesting_forbids_loops() {
+        let dialect = Dialect {
+            extensions: true,
+            max_nesting: 0,
+            ..Dialect::default()
+        };
+
+        for program in &["[+]", "+{-}", "++[>+<-]"] {
+            assert!(matches!(
+                Brainfuck::with_dialect(program, &dialect).err(),
+                Some(EvalError::TooDeep { depth: 0, .. })
+            ));
+        }
+
+        assert!(Brainfuck::with_dialect("+>+<~", &dialect).is_ok());
+    }
+
+    /// Splits every run of commands in a program into single commands, as the
+    /// interpreter ran them before runs were batched.
+    fn unbatched(bf: &Brainfuck) -> Brainfuck {
+        let mut starts = Vec::new();
+        let mut tokens = Vec::new();
+
+        for &token in &bf.tokens {
+            starts.push(tokens.len());
+            match token {
+                BrainfuckToken::Command { cmd, count } => {
+                    tokens.extend((0..count).map(|_| BrainfuckToken::from(cmd)))
                 }
-            } else {
-                return Ok(());
+                _ => tokens.push(token),
+            }
+        }
+
+        starts.push(tokens.len());
+        for token in &mut tokens {
+            match token {
+                BrainfuckToken::JumpIfZero { target }
+                | BrainfuckToken::JumpIfNonzero { target }
+                | BrainfuckToken::JumpIfNotMine { target }
+                | BrainfuckToken::JumpIfMine { target } => *target = starts[*target],
+                _ => {}
             }
         }
 
-        Err(EvalError::MaxSteps)
+        Brainfuck {
+            tokens,
+            ..bf.clone()
+        }
     }
 
-    /// Evaluates a Brainfuck string, and runs it.
-    pub fn eval(&mut self, str: &str, steps: u32) -> EvalResult<()> {
-        let backup = self.clone();
-        let res = self.run(Brainfuck::new(str)?, steps);
+    /// Runs a program both batched and unbatched, and checks that both end
+    /// with the same result, and the same board and step count if they
+    /// succeed. Failed moves are rolled back, so only their errors matter.
+    fn assert_same_as_unbatched(board: &GameBoard, bf: Brainfuck, steps: u32) {
+        let reference = unbatched(&bf);
+        let mut batched = board.clone();
+        let mut single = board.clone();
+        let res = batched.run(bf, steps, u32::MAX);
+
+        assert_eq!(res, single.run(reference, steps, u32::MAX));
+        if res.is_ok() {
+            assert_eq!(batched, single);
+            assert_eq!(batched.steps_used_last_move, single.steps_used_last_move);
+        }
+    }
 
-        if res.is_err() {
-            *self = backup;
-        } else {
-            self.next_turn();
+    #[test]
+    fn batching_matches_single_steps() {
+        let mut board = GameBoard::new_symmetric(4, 40, 0);
+        board.set_start(vec![(3, vec![O; 38])]).unwrap();
+
+        let programs = [
+            "++++[>+++<-]>[>++<-]",
+            "+++[>++++++[>++<-]<-]>>",
+            ">>>>",
+            "+++<",
+            ">>>+++",
+            "++++++++++[>>>+<<<-]",
+            "+[>+<]",
+        ];
+
+        for program in &programs {
+            for steps in 1..150 {
+                assert_same_as_unbatched(&board, Brainfuck::new(program).unwrap(), steps);
+            }
         }
 
-        res
+        // The overflow is reported at the same bucket, after the same steps.
+        let mut single = board.clone();
+        let bf = unbatched(&Brainfuck::new(">>>+++").unwrap());
+        assert_eq!(
+            single.run(bf, 100, u32::MAX),
+            Err(EvalError::Overflow { position: 3 })
+        );
+        assert_eq!(single.buckets[3].fill(), 40);
     }
 
-    /// Returns the number of players in the game.
-    pub fn player_count(&self) -> usize {
-        self.players.len()
+    proptest! {
+        #[test]
+        fn batching_matches_single_steps_on_random_boards(
+            board in arb_board(),
+            program in "[-+<>\\[\\]]{0,24}",
+            steps in 1..300u32,
+        ) {
+            if let Ok(bf) = Brainfuck::new(&program) {
+                assert_same_as_unbatched(&board, bf, steps);
+            }
+        }
     }
 
-    /// Returns the number of locked buckets.
-    pub fn locked_buckets(&self) -> usize {
-        self.iter().filter(|b| b.locked).count()
+    #[test]
+    fn untouched_buffer_bucket_is_skipped() {
+        let mut board = GameBoard::new_symmetric(3, 1, 1);
+        board.eval("+", 100).unwrap();
+        assert!(board.winners().is_none());
+        board.eval(">+", 100).unwrap();
+
+        assert!(board.buckets[2].is_empty());
+        let winners = board.winners().unwrap();
+        assert_eq!(winners.winner_count(), 2);
     }
 
-    /// Returns the number of buckets that must be filled in order to win.
-    pub fn win_bucket_count(&self) -> u16 {
-        self.bucket_count() as u16 - self.buffer_buckets
+    #[test]
+    fn builders_match_the_constructor() {
+        let board = GameBoard::new_symmetric(2, 2, 0)
+            .with_players(players("XOY"))
+            .with_buffer(1);
+        assert_eq!(board, GameBoard::new(vec![2, 2], 1, players("XOY")));
     }
 
-    /// Returns the winners of the game.
-    pub fn winners(&self) -> Option<Winners> {
-        use std::collections::hash_map::Entry::*;
+    #[test]
+    fn buckets_without_capacity_are_not_counted() {
+        let mut board = GameBoard::new(vec![1, 0, 1], 0, Players::default());
+        assert_eq!(board.lockable_buckets(), 2);
+        assert_eq!(board.win_bucket_count(), 2);
 
-        if (self.locked_buckets() as u16) < self.win_bucket_count() {
-            return None;
-        }
+        board.turn = 2;
+        board.eval("+", 100).unwrap();
+        assert!(board.winners().is_none());
+        board.eval(">>+", 100).unwrap();
+        assert!(board.winners().unwrap().is_draw());
+
+        let board = GameBoard::new(vec![1], 3, Players::default());
+        assert_eq!(board.win_bucket_count(), 0);
+    }
 
-        let mut counts = HashMap::with_capacity(self.player_count());
+    #[test]
+    fn owner_is_the_player_who_locked() {
+        let mut board = GameBoard::new_symmetric(2, 3, 1);
+        board.lock_rule = LockRule::Majority;
+        board.set_start(vec![(0, vec![O, X])]).unwrap();
+        board.eval("+", 100).unwrap();
+
+        // The bottom counter is O's, but X completed the majority.
+        let bucket = &board.buckets[0];
+        assert_eq!(bucket.counters, vec![O, X, X]);
+        assert_eq!(bucket.owner, Some(X));
+        assert_eq!(bucket.to_string(), "[1] OXX 3/3 ✓ X");
+        assert_eq!(board.winners().unwrap().sole_winner(), Some(X));
+        assert!(board.to_string().parse::<GameBoard>().unwrap().buckets[0].locked);
+
+        // Unlocking clears the owner.
+        board.unlock = true;
+        board.unlock_bucket(0).unwrap();
+        assert_eq!(board.buckets[0].owner, None);
+        assert_eq!(board.buckets[0].to_string(), "[1] OX_ 2/3");
+    }
 
-        // Computes the number of buckets each player owns.
-        for b in &self.buckets {
-            match counts.entry(b.counters[0]) {
-                Occupied(mut entry) => {
-                    *entry.get_mut() += 1;
-                }
+    #[test]
+    fn only_locked_buckets_show_owner() {
+        let mut board = GameBoard::new_symmetric(2, 2, 0);
+        board.eval("+", 100).unwrap();
+        assert_eq!(board.buckets[0].to_string(), "[1] X_ 1/2");
 
-                Vacant(entry) => {
-                    entry.insert(1);
-                }
-            }
+        board.buckets[0].label = None;
+        board.turn = 2;
+        board.eval("+", 100).unwrap();
+        assert_eq!(board.buckets[0].to_string(), "XX 2/2 ✓ X");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn eval_errors_round_trip_through_json() {
+        let err = EvalError::InvalidChar { c: 'é', idx: 3 };
+        let json = serde_json::to_string(&err).unwrap();
+        assert_eq!(json, r#"{"invalid_char":{"c":"é","idx":3}}"#);
+
+        let errors = [
+            err,
+            EvalError::OverBounds,
+            EvalError::OutOfCounters { player: X },
+            EvalError::DecrementLimit {
+                limit: DecrementLimit::PerGame(2),
+            },
+            EvalError::Length {
+                len: 4,
+                chars: 6,
+                turn: 3,
+            },
+        ];
+
+        for err in errors.iter() {
+            let json = serde_json::to_string(err).unwrap();
+            assert_eq!(serde_json::from_str::<EvalError>(&json).unwrap(), *err);
         }
+    }
 
-        let mut max_count = 0;
-        let mut winners = Default::default();
+    #[test]
+    fn map_indices_moves_string_indices_only() {
+        let shift = |err: EvalError| err.map_indices(|idx| idx + 3);
+
+        assert_eq!(
+            shift(EvalError::InvalidChar { c: 'x', idx: 1 }),
+            EvalError::InvalidChar { c: 'x', idx: 4 }
+        );
+        assert_eq!(
+            shift(EvalError::MixedBrackets { left: 0, right: 2 }),
+            EvalError::MixedBrackets { left: 3, right: 5 }
+        );
+        assert_eq!(
+            shift(EvalError::TooDeep { idx: 2, depth: 2 }),
+            EvalError::TooDeep { idx: 5, depth: 2 }
+        );
+        assert_eq!(
+            shift(EvalError::Overflow { position: 1 }),
+            EvalError::Overflow { position: 1 }
+        );
+        assert_eq!(shift(EvalError::MaxSteps), EvalError::MaxSteps);
+    }
 
-        // Computes the players tied for the greatest amount of buckets.
-        for (player, count) in counts.into_iter() {
-            match count.cmp(&max_count) {
-                Ordering::Greater => {
-                    max_count = count;
-                    winners = Winners::single(player);
-                }
+    #[test]
+    fn clearing_counts_as_decrementing() {
+        let extended = Dialect {
+            extensions: true,
+            ..Dialect::default()
+        };
+
+        let mut board = GameBoard::new_symmetric(3, 3, 0);
+        board.supply = Some(3);
+        board.decrement_limit = Some(DecrementLimit::PerGame(5));
+        board.eval("+", 100).unwrap();
+        board.eval("+", 100).unwrap();
+        assert_eq!(board.stock(0), Some(2));
+
+        // Both counters are removed, and X gets theirs back.
+        board.eval_with("~", 100, &extended).unwrap();
+        assert!(board.buckets[0].is_empty());
+        assert_eq!(board.stock(0), Some(3));
+        assert_eq!(board.decrements[0], 2);
+    }
 
-                Ordering::Equal => {
-                    winners.push(player);
-                }
+    #[test]
+    fn forced_locks_stay_locked() {
+        let mut board = GameBoard::new_symmetric(2, 3, 0);
+        board.edit_place(0, X, 1).unwrap();
+        board.edit_lock(0, true).unwrap();
+        assert!(board.buckets[0].forced);
+        assert_rollback(
+            &board,
+            "+",
+            &Dialect::default(),
+            EvalError::LockedIncr { position: 0 },
+        );
+
+        // The lock survives being shown and read back.
+        let parsed: GameBoard = board.to_string().parse().unwrap();
+        assert_eq!(parsed.buckets, board.buckets);
+
+        // Recomputing the lock undoes the edit.
+        board.edit_lock(0, false).unwrap();
+        assert!(!board.buckets[0].locked && !board.buckets[0].forced);
+    }
 
-                _ => {}
+    #[cfg(feature = "serde")]
+    #[test]
+    fn forced_locks_round_trip_through_json() {
+        let mut bucket = Bucket::new(3);
+        bucket.counters.push(X);
+        bucket.force_lock(0).unwrap();
+
+        let json = serde_json::to_string(&bucket).unwrap();
+        assert_eq!(serde_json::from_str::<Bucket>(&json).unwrap(), bucket);
+
+        // Buckets that weren't forced are written as before.
+        let json = serde_json::to_string(&Bucket::new(3)).unwrap();
+        assert!(!json.contains("forced"));
+    }
+
+    #[test]
+    fn invariants_catch_bad_locks_and_counts() {
+        let broken = |f: fn(&mut GameBoard)| {
+            let mut board = GameBoard::new_symmetric(3, 2, 0);
+            f(&mut board);
+            board.check_invariants().is_err()
+        };
+
+        // A lock needs a full bucket and the owner the lock rule gives.
+        assert!(broken(|b| {
+            b.buckets[0].counters = vec![X];
+            b.buckets[0].locked = true;
+            b.buckets[0].owner = Some(X);
+        }));
+        assert!(broken(|b| {
+            b.buckets[0].counters = vec![X, X];
+            b.buckets[0].locked = true;
+            b.buckets[0].owner = Some(O);
+        }));
+        assert!(!broken(|b| {
+            b.edit_place(0, X, 1).unwrap();
+            b.edit_lock(0, true).unwrap();
+        }));
+
+        // Counts must fit the players, the limits and the turn.
+        assert!(broken(|b| b.handicaps = vec![0, 0, 1]));
+        assert!(broken(|b| {
+            b.decrement_limit = Some(DecrementLimit::PerGame(1));
+            b.decrements = vec![2];
+        }));
+        assert!(broken(|b| {
+            b.overtime = true;
+            b.overtime_turn = Some(3);
+        }));
+        assert!(broken(|b| {
+            b.shrink = Some(1);
+            b.turn = 3;
+            b.shrinkage = vec![2];
+        }));
+    }
+
+    #[test]
+    fn specs_expand_groups() {
+        assert_eq!(
+            parse_spec("10x5,5x3"),
+            Ok([vec![10; 5], vec![5; 3]].concat())
+        );
+        assert_eq!(parse_spec("10,8,6"), Ok(vec![10, 8, 6]));
+        assert_eq!(parse_spec(" 4 , 2x2 "), Ok(vec![4, 2, 2]));
+    }
+
+    #[test]
+    fn spec_errors_name_the_token() {
+        let err = parse_spec("10x5,ax3").unwrap_err();
+        assert_eq!(
+            err,
+            SpecError::InvalidCapacity {
+                token: "ax3".to_owned()
             }
-        }
+        );
+        assert!(err.to_string().contains("\"ax3\""));
+
+        let err = parse_spec("10,5xb").unwrap_err();
+        assert!(err.to_string().contains("\"5xb\""));
+    }
+
+    #[test]
+    fn move_batches_return_each_board() {
+        let mut board = GameBoard::new_symmetric(3, 2, 0);
+        let boards = board.apply_move_batch(&[(X, "+"), (O, ">+")], 100).unwrap();
+
+        assert_eq!(boards.len(), 2);
+        assert_eq!(boards[0].turn, 1);
+        assert_eq!(boards[1], board);
+        assert_eq!(board.turn, 2);
+    }
+
+    #[test]
+    fn failed_move_batches_roll_back() {
+        let board = GameBoard::new_symmetric(3, 2, 0);
+        let err = board.clone().eval("+[", 100).unwrap_err();
+
+        let mut after = board.clone();
+        assert_eq!(
+            after.apply_move_batch(&[(X, "+"), (O, "+["), (X, ">+")], 100),
+            Err((O, err))
+        );
+        assert_eq!(after, board);
+    }
+
+    #[test]
+    fn move_batches_follow_the_turn_order() {
+        let board = GameBoard::new_symmetric(3, 2, 0);
+        let mut after = board.clone();
+
+        assert_eq!(
+            after.apply_move_batch(&[(X, "+"), (X, ">+")], 100),
+            Err((X, EvalError::WrongPlayer { expected: O }))
+        );
+        assert_eq!(after, board);
+    }
+
+    #[test]
+    fn chars_tokenize_like_strings() {
+        let program = "+> [-] <";
+        let bf = Brainfuck::from_chars(program.chars()).unwrap();
+        assert_eq!(bf.tokens(), Brainfuck::new(program).unwrap().tokens());
+        assert!(Brainfuck::from_chars(None).unwrap().is_empty());
+
+        // Error indices still count whitespace.
+        assert_eq!(
+            Brainfuck::from_chars("+ [".chars()).unwrap_err(),
+            Brainfuck::new("+ [").unwrap_err()
+        );
+    }
+
+    #[test]
+    fn shrink_locks_end_overtime() {
+        let mut board = GameBoard::new(vec![1, 1, 3, 3], 2, Players::default());
+        board.overtime = true;
+        board.shrink = Some(1);
+        board
+            .set_start(vec![(0, vec![X]), (1, vec![O]), (2, vec![X])])
+            .unwrap();
+        board.turn = 8;
+
+        // The tie starts overtime.
+        board.eval(">>+", 100).unwrap();
+        assert_eq!(board.overtime_turn, Some(8));
+        board.eval(">+", 100).unwrap();
+
+        // The third bucket locks as it shrinks, which is the first lock
+        // since overtime started.
+        assert_eq!(board.shrunk, vec![2, 3]);
+        assert_eq!(board.new_locks, vec![2]);
+        assert_eq!(board.winners().unwrap().sole_winner(), Some(X));
+    }
+
+    #[test]
+    fn adjacent_locks_need_an_earlier_lock() {
+        let mut board = GameBoard::new_symmetric(3, 1, 0);
+        board.turn = 8;
+        board.eval("+>+", 100).unwrap();
+        assert_eq!(board.new_locks, vec![0, 1]);
+        assert!(!board.adjacent_lock());
+
+        // The neighbor is the opponent's.
+        board.eval(">+", 100).unwrap();
+        assert!(!board.adjacent_lock());
+
+        let mut board = GameBoard::new_symmetric(3, 1, 0);
+        board.set_start(vec![(0, vec![X])]).unwrap();
+        board.turn = 8;
+        board.eval(">+", 100).unwrap();
+        assert!(board.adjacent_lock());
+    }
 
-        Some(winners)
+    #[test]
+    fn bonus_moves_settle_the_board() {
+        let mut board = GameBoard::new_symmetric(2, 2, 0);
+        board.gravity = true;
+        board.set_handicap(0, 2);
+        board.turn = 8;
+
+        board.eval(">+", 100).unwrap();
+        assert_eq!(board.buckets[0].counters, vec![X]);
+        assert_eq!(board.handicaps[0], 1);
+
+        // The counter falls and locks the first bucket, but it's still O's
+        // turn.
+        board.eval_extra("+", 100, &Dialect::default()).unwrap();
+        assert!(board.buckets[0].locked);
+        assert!(board.buckets[1].is_empty());
+        assert_eq!(board.new_locks, vec![0]);
+        assert_eq!(board.handicaps[0], 0);
+        assert_eq!(board.turn, 9);
     }
 }
@@ -2,20 +2,34 @@
 //! A fun litle game you can play on Discord.
 
 use std::env;
+use std::sync::Arc;
 
 use game::GameBoard;
-use play::{GameHandler, GamesMap};
+use play::{ChannelAllowlists, GameHandler, GamesMap, GuildDefaults, Perf, Puzzles};
 
 use serenity::prelude::*;
 
+mod defaults;
 mod game;
+mod notation;
+mod perf;
 mod play;
+mod puzzle;
+mod sim;
+mod storage;
+mod tape;
 
 #[tokio::main]
 async fn main() {
     // Configure the client with your Discord bot token in the environment.
     let token = env::var("DISCORD_TOKEN").expect("Expected a token in the environment");
 
+    // Loads the bundled puzzles, making sure they're all valid.
+    let puzzles = match puzzle::load() {
+        Ok(puzzles) => puzzles,
+        Err(why) => panic!("Invalid puzzle: {}", why),
+    };
+
     // Create a new instance of the Client, logging in as a bot. This will
     // automatically prepend your bot token with "Bot ", which is a requirement
     // by Discord for bot users.
@@ -27,6 +41,10 @@ async fn main() {
     {
         let mut data = client.data.write().await;
         data.insert::<GamesMap>(Default::default());
+        data.insert::<Puzzles>(Arc::new(puzzles));
+        data.insert::<Perf>(Default::default());
+        data.insert::<GuildDefaults>(storage::load_all(defaults::KIND));
+        data.insert::<ChannelAllowlists>(storage::load_all(play::ALLOWLIST_KIND));
     }
 
     // Finally, start a single shard, and start listening to events.
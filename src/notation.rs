@@ -0,0 +1,386 @@
+//! A PGN-like notation for game records, such as `1. X "+>" 2. O "-"`.
+//! Moves other than code are written as words, such as `2. O swap` or
+//! `5. X unlock 3`.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+use crate::game::{Dialect, EvalError, GameBoard, Player};
+
+/// What a player did in a recorded move.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Played some Brainfuck code, which is empty for skips.
+    Code(String),
+
+    /// Played a bonus move right after the move that earned it, without
+    /// passing the turn.
+    Bonus(String),
+
+    /// Took over the first player's position, under the swap rule.
+    Swap,
+
+    /// Unlocked the bucket at the given index.
+    Unlock(usize),
+
+    /// Won the bidding for the first move, with the given number of
+    /// handicapped moves. This doesn't pass the turn.
+    Handicap(usize),
+}
+
+/// Writes a string in quotes, escaping any quotes and backslashes.
+fn write_quoted(f: &mut Formatter, str: &str) -> FmtResult {
+    write!(f, "\"")?;
+
+    for c in str.chars() {
+        if c == '"' || c == '\\' {
+            write!(f, "\\")?;
+        }
+
+        write!(f, "{}", c)?;
+    }
+
+    write!(f, "\"")
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Self::Code(code) => write_quoted(f, code),
+            Self::Bonus(code) => {
+                write!(f, "bonus ")?;
+                write_quoted(f, code)
+            }
+            Self::Swap => write!(f, "swap"),
+            Self::Unlock(position) => write!(f, "unlock {}", position + 1),
+            Self::Handicap(moves) => write!(f, "handicap {}", moves),
+        }
+    }
+}
+
+/// A single move in a game record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordedMove {
+    /// The turn number of the move, starting from 1. Bonus moves share the
+    /// number of the move that earned them, and handicaps that of the first
+    /// move.
+    pub turn: u32,
+
+    /// The player who made the move.
+    pub player: Player,
+
+    /// What the player did.
+    pub action: Action,
+}
+
+impl Display for RecordedMove {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}. {} {}", self.turn, self.player, self.action)
+    }
+}
+
+/// The moves of a game, in order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Record(pub Vec<RecordedMove>);
+
+impl Display for Record {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        for (idx, mv) in self.0.iter().enumerate() {
+            if idx != 0 {
+                write!(f, " ")?;
+            }
+
+            write!(f, "{}", mv)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Skips over any whitespace.
+fn skip_whitespace(chars: &mut Peekable<CharIndices>) {
+    while chars.peek().is_some_and(|(_, c)| c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+/// Reads characters while they satisfy the given predicate.
+fn read_while<F: Fn(char) -> bool>(chars: &mut Peekable<CharIndices>, f: F) -> String {
+    let mut res = String::new();
+    while let Some(&(_, c)) = chars.peek() {
+        if !f(c) {
+            break;
+        }
+
+        res.push(c);
+        chars.next();
+    }
+
+    res
+}
+
+/// Reads a quoted string, undoing any escapes, for the given turn.
+fn read_quoted(chars: &mut Peekable<CharIndices>, turn: u32) -> Result<String, String> {
+    skip_whitespace(chars);
+    if !matches!(chars.next(), Some((_, '"'))) {
+        return Err(format!("expected a quoted move in turn {}", turn));
+    }
+
+    let mut res = String::new();
+    loop {
+        match chars.next() {
+            Some((_, '"')) => return Ok(res),
+            Some((_, '\\')) => match chars.next() {
+                Some((_, c)) => res.push(c),
+                None => return Err(format!("unterminated move in turn {}", turn)),
+            },
+            Some((_, c)) => res.push(c),
+            None => return Err(format!("unterminated move in turn {}", turn)),
+        }
+    }
+}
+
+/// Reads a number after a word such as `unlock`, for the given turn.
+fn read_number(chars: &mut Peekable<CharIndices>, turn: u32) -> Result<usize, String> {
+    skip_whitespace(chars);
+    read_while(chars, |c| c.is_ascii_digit())
+        .parse()
+        .map_err(|_| format!("expected a number in turn {}", turn))
+}
+
+/// Checks that a move has the given turn number, and is played by whoever
+/// plays that turn.
+fn check_turn(mv: &RecordedMove, turn: u32, board: &GameBoard) -> Result<(), String> {
+    if mv.turn != turn {
+        return Err(format!("expected turn {}, found turn {}", turn, mv.turn));
+    }
+
+    let expected = board.players[board.players.idx(turn - 1)];
+    if mv.player != expected {
+        return Err(format!(
+            "turn {} should be played by {}, not {}",
+            mv.turn, expected, mv.player
+        ));
+    }
+
+    Ok(())
+}
+
+impl Record {
+    /// Returns whether no moves have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Adds a move to the record.
+    pub fn push(&mut self, turn: u32, player: Player, action: Action) {
+        self.0.push(RecordedMove {
+            turn,
+            player,
+            action,
+        });
+    }
+
+    /// Parses a game record.
+    pub fn parse(str: &str) -> Result<Self, String> {
+        let mut moves = Vec::new();
+        let mut chars = str.char_indices().peekable();
+
+        loop {
+            skip_whitespace(&mut chars);
+            let start = match chars.peek() {
+                Some(&(idx, _)) => idx,
+                None => return Ok(Self(moves)),
+            };
+
+            // Reads the turn number.
+            let turn = read_while(&mut chars, |c| c.is_ascii_digit())
+                .parse()
+                .map_err(|_| format!("expected a turn number at index {}", start + 1))?;
+
+            match chars.next() {
+                Some((_, '.')) => {}
+                _ => return Err(format!("expected a period after turn {}", turn)),
+            }
+
+            // Reads the player.
+            skip_whitespace(&mut chars);
+            let player = match chars.next() {
+                Some((_, c)) if c != '"' => Player::new(c)
+                    .map_err(|err| format!("invalid player in turn {}: {}", turn, err))?,
+                _ => return Err(format!("expected a player in turn {}", turn)),
+            };
+
+            // Reads the quoted code, or the word for any other action.
+            skip_whitespace(&mut chars);
+            let action = match read_while(&mut chars, |c| c.is_ascii_alphabetic()).as_str() {
+                "" => Action::Code(read_quoted(&mut chars, turn)?),
+                "bonus" => Action::Bonus(read_quoted(&mut chars, turn)?),
+                "swap" => Action::Swap,
+                "unlock" => match read_number(&mut chars, turn)?.checked_sub(1) {
+                    Some(position) => Action::Unlock(position),
+                    None => return Err(format!("buckets count from 1 in turn {}", turn)),
+                },
+                "handicap" => Action::Handicap(read_number(&mut chars, turn)?),
+                word => return Err(format!("unknown action {} in turn {}", word, turn)),
+            };
+
+            moves.push(RecordedMove {
+                turn,
+                player,
+                action,
+            });
+        }
+    }
+
+    /// Plays every move in the record on the given board, checking that the
+    /// turn numbers and players match. Moves with invalid characters are
+    /// played as empty moves, and their turn numbers are returned.
+    pub fn replay(
+        &self,
+        board: &mut GameBoard,
+        steps: u32,
+        dialect: &Dialect,
+    ) -> Result<Vec<u32>, String> {
+        let mut skipped = Vec::new();
+        let mut moves = self.0.iter().peekable();
+
+        while let Some(mv) = moves.next() {
+            // Bonus moves are played by whoever just moved.
+            let turn = match mv.action {
+                Action::Bonus(_) => board.turn,
+                _ => board.turn + 1,
+            };
+
+            if turn == 0 {
+                return Err(format!("turn {} has a bonus move before any move", mv.turn));
+            }
+
+            check_turn(mv, turn, board)?;
+            let invalid = |err| format!("turn {} is invalid: {}", mv.turn, err);
+
+            match &mv.action {
+                // Plays the whole run of ordinary moves at once. Each of them
+                // passes the turn, so they can be checked beforehand.
+                Action::Code(code) => {
+                    let mut run = vec![(mv, code.as_str())];
+                    while let Some(next) = moves.peek() {
+                        match &next.action {
+                            Action::Code(code) => {
+                                check_turn(next, turn + run.len() as u32, board)?;
+                                run.push((next, code));
+                                moves.next();
+                            }
+                            _ => break,
+                        }
+                    }
+
+                    let codes: Vec<_> = run.iter().map(|&(_, code)| code).collect();
+                    for (&(mv, _), res) in
+                        run.iter().zip(board.eval_all_with(&codes, steps, dialect))
+                    {
+                        match res {
+                            Ok(()) => {}
+                            Err(EvalError::InvalidChar { .. }) => skipped.push(mv.turn),
+                            Err(err) => {
+                                return Err(format!("turn {} is invalid: {}", mv.turn, err))
+                            }
+                        }
+                    }
+                }
+
+                Action::Bonus(code) => board.eval_extra(code, steps, dialect).map_err(invalid)?,
+
+                Action::Swap => {
+                    if board.turn != 1 || board.player_count() != 2 {
+                        return Err(format!("turn {} can't be a swap", mv.turn));
+                    }
+
+                    board.swap_first_players();
+                }
+
+                Action::Unlock(position) => {
+                    if !board.unlock {
+                        return Err(format!(
+                            "turn {} unlocks a bucket, but unlocking is off",
+                            mv.turn
+                        ));
+                    }
+
+                    board.unlock_bucket(*position).map_err(invalid)?;
+                }
+
+                Action::Handicap(count) => board.set_handicap(board.player_idx(), *count),
+            }
+        }
+
+        Ok(skipped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_round_trip() {
+        let str = r#"1. X handicap 2 1. X "+\"" 2. O swap 3. O "" 4. X unlock 3 4. X bonus ">""#;
+        let record = Record::parse(str).unwrap();
+        assert_eq!(record.0[3].action, Action::Code(String::new()));
+        assert_eq!(record.0[4].action, Action::Unlock(2));
+        assert_eq!(record.to_string(), str);
+
+        assert!(Record::parse("1. X unlock 0").is_err());
+        assert!(Record::parse("1. X pass").is_err());
+    }
+
+    #[test]
+    fn replays_every_action() {
+        let mut board = GameBoard::new_symmetric(3, 2, 0);
+        board.unlock = true;
+
+        let record = Record::parse(
+            r#"1. X handicap 1 1. X "" 2. O swap 3. O "++" 4. X unlock 1 5. O "+" 5. O bonus ">""#,
+        )
+        .unwrap();
+        assert_eq!(
+            record.replay(&mut board, 100, &Dialect::default()),
+            Ok(Vec::new())
+        );
+
+        // O took over X's position, locked the first bucket twice, and moved on.
+        assert_eq!(board.turn, 5);
+        assert_eq!(board.players[0].to_string(), "O");
+        assert_eq!(board.buckets[0].owner, board.players.iter().next().copied());
+        assert_eq!(board.position, 1);
+    }
+
+    #[test]
+    fn misplaced_actions_are_rejected() {
+        let dialect = Dialect::default();
+        let replay = |str: &str| {
+            let mut board = GameBoard::new_symmetric(3, 2, 0);
+            Record::parse(str)
+                .unwrap()
+                .replay(&mut board, 100, &dialect)
+        };
+
+        assert_eq!(
+            replay("1. X swap"),
+            Err("turn 1 can't be a swap".to_owned())
+        );
+        assert_eq!(
+            replay(r#"1. X bonus "+""#),
+            Err("turn 1 has a bonus move before any move".to_owned())
+        );
+        assert_eq!(
+            replay(r#"1. X "+" 2. O unlock 1"#),
+            Err("turn 2 unlocks a bucket, but unlocking is off".to_owned())
+        );
+        assert_eq!(
+            replay(r#"1. X "+" 2. O "+" 2. O "+""#),
+            Err("expected turn 3, found turn 2".to_owned())
+        );
+    }
+}
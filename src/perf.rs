@@ -0,0 +1,148 @@
+//! Timing statistics for the evaluation of moves.
+
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// The number of recent evaluations kept for each channel.
+pub const RECENT_EVALUATIONS: usize = 32;
+
+/// Formats a duration in milliseconds.
+pub fn fmt_duration(duration: Duration) -> String {
+    format!("{:.2} ms", duration.as_secs_f64() * 1000.0)
+}
+
+/// A single timed evaluation of a move.
+#[derive(Clone, Copy, Debug)]
+pub struct Evaluation {
+    /// The turn the move was played on, starting from 1.
+    pub turn: u32,
+
+    /// How long the evaluation took.
+    pub duration: Duration,
+
+    /// The number of steps the program ran for. This is the step limit if
+    /// it ran out of steps, and zero if it failed for any other reason.
+    pub steps: u32,
+
+    /// Whether the program ran out of steps.
+    pub max_steps: bool,
+}
+
+impl Display for Evaluation {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f,
+            "Turn {}: {}, {} steps",
+            self.turn,
+            fmt_duration(self.duration),
+            self.steps
+        )?;
+
+        if self.max_steps {
+            write!(f, " (out of steps)")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The latest evaluations in a channel.
+#[derive(Clone, Debug, Default)]
+pub struct RecentEvaluations {
+    /// The last few evaluations, from oldest to newest.
+    entries: VecDeque<Evaluation>,
+
+    /// The number of programs that ran out of steps since the bot started.
+    max_steps_failures: usize,
+
+    /// The last evaluation, if it hasn't yet been added to the global
+    /// counters.
+    unreported: Option<Evaluation>,
+}
+
+impl RecentEvaluations {
+    /// Records an evaluation, forgetting the oldest one if needed.
+    pub fn push(&mut self, evaluation: Evaluation) {
+        if self.entries.len() == RECENT_EVALUATIONS {
+            self.entries.pop_front();
+        }
+
+        if evaluation.max_steps {
+            self.max_steps_failures += 1;
+        }
+
+        self.entries.push_back(evaluation);
+        self.unreported = Some(evaluation);
+    }
+
+    /// Returns the last evaluation if it hasn't been returned before, so that
+    /// it can be added to the global counters.
+    pub fn take_unreported(&mut self) -> Option<Evaluation> {
+        self.unreported.take()
+    }
+
+    /// Returns the slowest of the recent evaluations, slowest first.
+    pub fn slowest(&self, count: usize) -> Vec<Evaluation> {
+        let mut entries: Vec<_> = self.entries.iter().copied().collect();
+        entries.sort_by_key(|e| Reverse(e.duration));
+        entries.truncate(count);
+        entries
+    }
+
+    /// Returns the average duration of the recent evaluations.
+    pub fn average(&self) -> Option<Duration> {
+        let total: Duration = self.entries.iter().map(|e| e.duration).sum();
+        (!self.entries.is_empty()).then(|| total / self.entries.len() as u32)
+    }
+
+    /// Returns the number of programs that ran out of steps in the channel.
+    pub fn max_steps_failures(&self) -> usize {
+        self.max_steps_failures
+    }
+}
+
+/// Counters over every evaluation in every channel since the bot started.
+#[derive(Debug, Default)]
+pub struct PerfCounters {
+    /// The number of evaluations.
+    evaluations: AtomicU64,
+
+    /// The total time spent on evaluations, in nanoseconds.
+    nanos: AtomicU64,
+
+    /// The number of programs that ran out of steps.
+    max_steps_failures: AtomicU64,
+}
+
+impl PerfCounters {
+    /// Adds an evaluation to the counters.
+    pub fn record(&self, evaluation: &Evaluation) {
+        let nanos = evaluation.duration.as_nanos().min(u64::MAX as u128) as u64;
+        self.evaluations.fetch_add(1, Ordering::Relaxed);
+        self.nanos.fetch_add(nanos, Ordering::Relaxed);
+
+        if evaluation.max_steps {
+            self.max_steps_failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the number of evaluations.
+    pub fn evaluations(&self) -> u64 {
+        self.evaluations.load(Ordering::Relaxed)
+    }
+
+    /// Returns the average duration of an evaluation.
+    pub fn average(&self) -> Option<Duration> {
+        let evaluations = self.evaluations();
+        (evaluations != 0)
+            .then(|| Duration::from_nanos(self.nanos.load(Ordering::Relaxed) / evaluations))
+    }
+
+    /// Returns the number of programs that ran out of steps.
+    pub fn max_steps_failures(&self) -> u64 {
+        self.max_steps_failures.load(Ordering::Relaxed)
+    }
+}
@@ -1,18 +1,136 @@
-use std::collections::HashMap;
-use std::fmt::Display;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::env;
+use std::fmt::{Display, Write};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use crate::defaults::{self, Defaults};
 use crate::game::*;
+use crate::notation::{Action, Record};
+use crate::perf::{fmt_duration, Evaluation, PerfCounters, RecentEvaluations};
+use crate::puzzle::Puzzle;
+use crate::sim::{new_agent, simulate, Rng, SimRules, AGENTS};
+use crate::storage;
+use crate::tape::Tape;
 
+use dashmap::DashMap;
 use serenity::http::Http;
-use serenity::model::id::{ChannelId, UserId};
-use serenity::model::{channel::Message, gateway::Ready};
+use serenity::model::channel::{Message, Reaction, ReactionType};
+use serenity::model::gateway::Ready;
+use serenity::model::id::{ChannelId, GuildId, MessageId, UserId};
+use serenity::model::user::User;
 use serenity::{async_trait, prelude::*};
+use uuid::Uuid;
 
 use crate::{game::EvalError, GameBoard};
 
 const MAX_STEPS: u32 = 10_000_000;
 const ROLE_ID: u64 = 864243710576689223;
+const MAX_SIMULATIONS: usize = 1000;
+const MAX_FAILED_MOVES: usize = 5;
+const BONUS_TIMEOUT: Duration = Duration::from_secs(60);
+const CHALLENGE_TIMEOUT: Duration = Duration::from_secs(300);
+const MAX_BUCKETS: usize = 64;
+const MAX_CAPACITY: usize = 1000;
+const MAX_TOTAL_CAPACITY: usize = 10_000;
+const MAX_GAMES: usize = 8;
+
+/// The short ID of the game that holds the settings of a channel.
+const PRIMARY_GAME: u32 = 1;
+
+/// The names of the commands, which are logged when used.
+const COMMANDS: [&str; 26] = [
+    "set",
+    "settings",
+    "play",
+    "board",
+    "edit",
+    "stats",
+    "score",
+    "reset",
+    "bid",
+    "export",
+    "import",
+    "debug",
+    "legal",
+    "check",
+    "length",
+    "puzzle",
+    "solve",
+    "simulate",
+    "perf",
+    "define",
+    "bf",
+    "defaults",
+    "bfchannel",
+    "challenge",
+    "accept",
+    "decline",
+];
+
+/// The maximum number of steps an ordinary Brainfuck program runs for.
+const MAX_BF_STEPS: u32 = 1_000_000;
+
+/// The maximum number of characters of output posted for an ordinary
+/// Brainfuck program.
+const MAX_BF_OUTPUT: usize = 1500;
+
+/// The maximum number of macros in a channel.
+const MAX_MACROS: usize = 32;
+
+/// The maximum length of a macro's name and of its code.
+const MAX_MACRO_NAME: usize = 16;
+const MAX_MACRO_LEN: usize = 200;
+
+/// The number of slow evaluations listed by the "perf" command.
+const SLOWEST_EVALUATIONS: usize = 5;
+
+/// The largest board for which the "legal" command lists the buckets that are
+/// one counter away from locking.
+const MAX_NEAR_LOCK_BUCKETS: usize = 16;
+
+/// The directory the channel allowlists are stored in, within the data
+/// directory.
+pub const ALLOWLIST_KIND: &str = "channels";
+
+/// The reply to any attempt to change the settings mid-game.
+const SETTINGS_LOCKED: &str = "Cannot change settings during an active game — use `reset` first.";
+
+/// The reactions added to the board after each move, and the commands they
+/// stand for.
+const REACTION_MOVES: [(&str, &str); 4] = [("➕", "+"), ("➖", "-"), ("⬅️", "<"), ("➡️", ">")];
+
+/// Returns the ID of the role allowed to run administrative commands, read
+/// from the `ADMIN_ROLE_ID` environment variable.
+fn admin_role_id() -> Option<u64> {
+    env::var("ADMIN_ROLE_ID").ok()?.parse().ok()
+}
+
+/// Returns the board new channels start with. Its number of buckets and
+/// their capacity may be set through the `DEFAULT_BUCKET_COUNT` and
+/// `DEFAULT_BUCKET_CAPACITY` environment variables, and otherwise, or if the
+/// board would be empty or too large, the usual defaults are used.
+fn default_board() -> GameBoard {
+    let var = |name, default| {
+        env::var(name)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(default)
+    };
+
+    let count = var("DEFAULT_BUCKET_COUNT", DEFAULT_BUCKET_COUNT);
+    let capacity = var("DEFAULT_BUCKET_CAPACITY", DEFAULT_BUCKET_CAPACITY);
+
+    if count == 0
+        || count > MAX_BUCKETS
+        || capacity > MAX_CAPACITY
+        || count * capacity > MAX_TOTAL_CAPACITY
+    {
+        GameBoard::default()
+    } else {
+        GameBoard::new_symmetric(count, capacity, DEFAULT_BUFFER_BUCKETS)
+    }
+}
 
 /// Formats a string, but adds triple backticks.
 macro_rules! format_md {
@@ -25,31 +143,264 @@ macro_rules! format_md {
     };
 }
 
-/// A map from channels into games.
+/// Parses the short ID of a game, with or without a leading `#`.
+fn parse_game(component: &str) -> Option<u32> {
+    component
+        .strip_prefix('#')
+        .unwrap_or(component)
+        .parse()
+        .ok()
+}
+
+/// Parses a move that names its game, either with a prefix like `#2` or
+/// afterwards like `skip 2`. Returns the game, the move itself, and the number
+/// of characters stripped from the start of the message.
+fn parse_named_move(content: &str) -> Option<(u32, &str, usize)> {
+    let mut components = content.split_whitespace();
+    match (components.next(), components.next(), components.next()) {
+        (Some(first), _, _) if first.starts_with('#') => parse_game(first).map(|game| {
+            let rest = content.trim_start()[first.len()..].trim_start();
+            let prefix = &content[..content.len() - rest.len()];
+            (game, rest, prefix.chars().count())
+        }),
+        (Some("skip"), Some(game), None) => parse_game(game).map(|game| (game, "skip", 0)),
+        _ => None,
+    }
+}
+
+/// Parses a 1-indexed bucket number into a bucket index.
+fn parse_bucket(component: Option<&str>) -> Option<usize> {
+    component?.parse::<usize>().ok()?.checked_sub(1)
+}
+
+/// Returns whether a board whose buckets, across both tapes, have the given
+/// capacities would be too large to play on.
+fn board_too_large(capacities: &[usize]) -> bool {
+    capacities.len() > MAX_BUCKETS
+        || capacities.iter().any(|&c| c > MAX_CAPACITY)
+        || capacities.iter().sum::<usize>() > MAX_TOTAL_CAPACITY
+}
+
+/// Parses a player symbol, which must be a single character.
+fn parse_player(component: Option<&str>) -> Option<Player> {
+    let mut chars = component?.chars();
+    let c = chars.next()?;
+    if chars.next().is_some() {
+        return None;
+    }
+
+    Player::new(c).ok()
+}
+
+/// Applies an `edit` subcommand to the board. Returns `None` if the
+/// subcommand couldn't be parsed.
+fn edit_board<'a>(
+    board: &mut GameBoard,
+    mut components: impl Iterator<Item = &'a str>,
+) -> Option<Result<(), EditError>> {
+    Some(match components.next()? {
+        "place" => {
+            let position = parse_bucket(components.next())?;
+            let player = parse_player(components.next())?;
+            let count = components.next()?.parse().ok()?;
+            board.edit_place(position, player, count)
+        }
+
+        "remove" => {
+            let position = parse_bucket(components.next())?;
+            let count = components.next()?.parse().ok()?;
+            board.edit_remove(position, count)
+        }
+
+        "pointer" => board.edit_pointer(parse_bucket(components.next())?),
+
+        "turn" => {
+            board.turn = components.next()?.parse::<u32>().ok()?.checked_sub(1)?;
+            Ok(())
+        }
+
+        "lock" => {
+            let position = parse_bucket(components.next())?;
+            let force = match components.next() {
+                None => false,
+                Some("force") => true,
+                Some(_) => return None,
+            };
+
+            board.edit_lock(position, force)
+        }
+
+        _ => return None,
+    })
+}
+
+/// Formats a boolean setting.
+fn on_off(setting: bool) -> &'static str {
+    if setting {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+/// Returns whether a character may be part of a macro name.
+fn is_macro_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Writes a number with commas between groups of three digits.
+fn separate_thousands(n: u32) -> String {
+    let digits = n.to_string();
+    let mut res = String::new();
+
+    // The first group is the only one that may be shorter.
+    let first_group = digits.len() % 3;
+    for (idx, c) in digits.chars().enumerate() {
+        if idx != 0 && idx % 3 == first_group {
+            res.push(',');
+        }
+
+        res.push(c);
+    }
+
+    res
+}
+
+/// Formats the final scores of a game, from highest to lowest.
+fn format_scores(board: &GameBoard) -> String {
+    let mut scores = board.scores();
+    scores.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap());
+
+    let scores: Vec<_> = scores
+        .iter()
+        .map(|(player, score)| format!("{} {}", player, score))
+        .collect();
+
+    let mut res = format!("Final score: {}", scores.join(" – "));
+    if board.won_by_tie_break() {
+        res += ", decided by the counters in unlocked buckets";
+    }
+
+    res
+}
+
+/// A map from channels into their games, by short ID. The game with ID
+/// [`PRIMARY_GAME`] holds the settings of the channel, and the others are
+/// started with copies of them while it's busy.
+///
+/// Each channel is locked separately, and only while its games are looked
+/// up, so channels never wait on each other or on the global data lock.
 #[derive(Debug, Default)]
-pub struct GamesMap(HashMap<ChannelId, Arc<RwLock<GameConfig>>>);
+pub struct GamesMap(DashMap<ChannelId, BTreeMap<u32, Arc<RwLock<GameConfig>>>>);
 
 impl TypeMapKey for GamesMap {
-    type Value = Self;
+    type Value = Arc<Self>;
 }
 
 impl GamesMap {
-    // Returns a reference to the game config corresponding to the channel ID.
-    pub fn get(&self, id: ChannelId) -> Option<&Arc<RwLock<GameConfig>>> {
-        self.0.get(&id)
+    // Returns the game config corresponding to the channel ID and the short
+    // ID of the game.
+    pub fn get(&self, id: ChannelId, game: u32) -> Option<Arc<RwLock<GameConfig>>> {
+        self.0.get(&id)?.get(&game).cloned()
+    }
+
+    /// Returns the games of the channel with the given ID, by short ID.
+    pub fn games(&self, id: ChannelId) -> Vec<(u32, Arc<RwLock<GameConfig>>)> {
+        self.0.get(&id).map_or_else(Vec::new, |games| {
+            games
+                .iter()
+                .map(|(&game, lock)| (game, lock.clone()))
+                .collect()
+        })
+    }
+
+    /// Returns the primary game configuration of the channel with the given
+    /// ID, inserting the one returned by `f` if there's none.
+    pub fn get_or_insert_with<F: FnOnce() -> GameConfig>(
+        &self,
+        id: ChannelId,
+        f: F,
+    ) -> Arc<RwLock<GameConfig>> {
+        self.0
+            .entry(id)
+            .or_default()
+            .entry(PRIMARY_GAME)
+            .or_insert_with(|| Arc::new(RwLock::new(f())))
+            .clone()
+    }
+
+    /// Adds a game to the channel with the given ID, and returns the short ID
+    /// it was given, which is the lowest one not in use. Returns `None`
+    /// instead if the channel already has `max` games.
+    pub fn insert(&self, id: ChannelId, config: GameConfig, max: usize) -> Option<u32> {
+        let mut games = self.0.entry(id).or_default();
+        if games.len() >= max {
+            return None;
+        }
+
+        let game = (PRIMARY_GAME..).find(|n| !games.contains_key(n)).unwrap();
+        games.insert(game, Arc::new(RwLock::new(config)));
+        Some(game)
     }
 
-    /// Inserts a new game configuration into the channel with the given ID.
-    pub fn insert(&mut self, id: ChannelId) -> &mut Arc<RwLock<GameConfig>> {
-        use std::collections::hash_map::Entry::*;
+    /// Removes every game of the channel with the given ID.
+    pub fn remove(&self, id: ChannelId) {
+        self.0.remove(&id);
+    }
 
-        match self.0.entry(id) {
-            Occupied(_) => panic!("Internal error: duplicated channel ID!"),
-            Vacant(entry) => entry.insert(Default::default()),
+    /// Removes a single game from the channel with the given ID.
+    pub fn remove_game(&self, id: ChannelId, game: u32) {
+        if let Some(mut games) = self.0.get_mut(&id) {
+            games.remove(&game);
         }
     }
 }
 
+/// The default settings of each guild, which new channels start with.
+pub struct GuildDefaults;
+
+impl TypeMapKey for GuildDefaults {
+    type Value = HashMap<GuildId, Defaults>;
+}
+
+/// The channels of each guild the bot plays in. Guilds without a list let it
+/// play anywhere.
+pub struct ChannelAllowlists;
+
+impl TypeMapKey for ChannelAllowlists {
+    type Value = HashMap<GuildId, BTreeSet<ChannelId>>;
+}
+
+/// The list of bundled puzzles.
+pub struct Puzzles;
+
+impl TypeMapKey for Puzzles {
+    type Value = Arc<Vec<Puzzle>>;
+}
+
+/// The timing statistics over every channel.
+pub struct Perf;
+
+impl TypeMapKey for Perf {
+    type Value = Arc<PerfCounters>;
+}
+
+/// The phase a game is in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum GameState {
+    /// No game is being played, and the settings may be changed.
+    Configuring,
+
+    /// The players are bidding for the first move.
+    Bidding {
+        /// The bids placed so far, in the order they were placed.
+        bids: Vec<(UserId, usize)>,
+    },
+
+    /// A game is currently being played.
+    Active,
+}
+
 /// Stores the current game and its configuration.
 #[derive(Debug)]
 pub struct GameConfig {
@@ -59,315 +410,3234 @@ pub struct GameConfig {
     /// The game board.
     board: GameBoard,
 
-    /// The user IDs of the players in turn.
-    player_ids: Vec<UserId>,
+    /// The variant of Brainfuck moves are written in.
+    dialect: Dialect,
+
+    /// The user IDs of the players in each seat, or `None` for the seats
+    /// that haven't been taken yet.
+    player_ids: Vec<Option<UserId>>,
+
+    /// The phase the game is in.
+    state: GameState,
+
+    /// Whether the players bid for the first move before the game starts.
+    bidding: bool,
+
+    /// Whether the second player may swap places with the first after the
+    /// first move.
+    swap: bool,
+
+    /// Whether the players swapped places in the current game.
+    swapped: bool,
+
+    /// The number of failed moves in the current turn.
+    failed_moves: usize,
+
+    /// Whether locking a bucket next to another one of yours grants a bonus
+    /// move.
+    adjacency: bool,
+
+    /// The user who may currently play a bonus move, if any.
+    bonus_pending: Option<UserId>,
 
-    /// Whether a game is currently being played.
-    active: bool,
+    /// When the pending bonus move expires.
+    bonus_deadline: Option<Instant>,
+
+    /// The last message showing the board, whose reactions count as moves.
+    board_message: Option<MessageId>,
+
+    /// Whether the record of a game is posted when it ends.
+    pgn: bool,
+
+    /// The moves of the current game. Swaps, unlocks and bonus moves aren't
+    /// recorded.
+    record: Record,
+
+    /// The moves of the last completed game.
+    last_record: Record,
+
+    /// The puzzle currently being solved, which is independent of the game.
+    puzzle: Option<Puzzle>,
+
+    /// How the counters on the board are drawn.
+    display_mode: DisplayMode,
+
+    /// The channel where moves and commands are logged, if any.
+    log_channel_id: Option<ChannelId>,
+
+    /// The number of games each user has won since the bot started. Tied
+    /// players all get the win.
+    win_counts: HashMap<UserId, u32>,
+
+    /// The timings of the latest moves in the channel.
+    perf: RecentEvaluations,
+
+    /// The snippets of code that `$name` expands to in moves, by name.
+    macros: BTreeMap<String, String>,
+
+    /// The identifier of the current or last game, which tags its log
+    /// entries. It's nil until the first game starts.
+    game_id: Uuid,
+
+    /// The channel the thread was started from, if the game is played in a
+    /// thread of its own.
+    parent_id: Option<ChannelId>,
+
+    /// The announcement of the winners of the last completed game.
+    last_result: Option<String>,
+
+    /// The challenge waiting for an answer, if any.
+    challenge: Option<Challenge>,
 }
 
 impl Default for GameConfig {
     fn default() -> Self {
         Self {
             steps: 1_000_000,
-            board: Default::default(),
+            board: default_board(),
+            dialect: Dialect::default(),
             player_ids: Vec::new(),
-            active: false,
+            state: GameState::Configuring,
+            bidding: false,
+            swap: false,
+            swapped: false,
+            failed_moves: 0,
+            adjacency: false,
+            bonus_pending: None,
+            bonus_deadline: None,
+            board_message: None,
+            pgn: false,
+            record: Record::default(),
+            last_record: Record::default(),
+            puzzle: None,
+            display_mode: DisplayMode::Plain,
+            log_channel_id: None,
+            win_counts: HashMap::new(),
+            perf: RecentEvaluations::default(),
+            macros: BTreeMap::new(),
+            game_id: Uuid::nil(),
+            parent_id: None,
+            last_result: None,
+            challenge: None,
         }
     }
 }
 
 impl GameConfig {
-    /// Evaluates a Brainfuck string, and runs it. Returns `None` if inactive.
-    fn eval(&mut self, str: &str) -> Option<EvalResult<()>> {
-        self.active.then(|| self.board.eval(str, self.steps))
+    /// Initializes a configuration with the given defaults, and everything
+    /// else as usual.
+    fn from_defaults(defaults: Defaults) -> Self {
+        Self {
+            steps: defaults.steps,
+            board: defaults.board,
+            dialect: defaults.dialect,
+            bidding: defaults.bidding,
+            swap: defaults.swap,
+            adjacency: defaults.adjacency,
+            pgn: defaults.pgn,
+            ..Default::default()
+        }
     }
 
-    /// Resets the game configuration to what it was before the game started.
-    fn reset(&mut self) {
-        self.active = false;
-        self.player_ids = Vec::new();
-        self.board.reset();
-    }
+    /// Returns the settings that can be saved as defaults, with the board at
+    /// its starting position.
+    fn defaults(&self) -> Defaults {
+        let mut board = self.board.clone();
+        board.reset();
 
-    /// Gets the user ID of the current player, or `None` if it hasn't yet been set.
-    fn id(&self) -> Option<UserId> {
-        self.player_ids.get(self.board.player_idx()).copied()
+        Defaults {
+            steps: self.steps,
+            board,
+            dialect: self.dialect.clone(),
+            bidding: self.bidding,
+            swap: self.swap,
+            adjacency: self.adjacency,
+            pgn: self.pgn,
+        }
     }
-}
 
-/// A helper struct whose associated methods wrap around some common operations.
-struct MessageHelper<'a> {
-    /// The context used to send messages.
-    ctx: &'a Context,
+    /// Returns a configuration with the same settings and starting position,
+    /// or those of the given preset, for a game played alongside this one, or
+    /// in a thread started from its channel.
+    fn copy_settings(&self, parent_id: Option<ChannelId>, preset: Option<Defaults>) -> Self {
+        let preset = match preset {
+            Some(preset) => preset,
+            None => {
+                let mut board = self.board.clone();
 
-    /// The ID of the channel in which messages are sent.
-    channel_id: ChannelId,
-}
+                // A game in progress is copied from its starting position.
+                if !self.is_configuring() {
+                    if self.swapped {
+                        board.players.swap(0, 1);
+                    }
+
+                    board.reset();
+                }
+
+                Defaults {
+                    board,
+                    ..self.defaults()
+                }
+            }
+        };
 
-impl<'a> MessageHelper<'a> {
-    /// Initializes a new message helper.
-    fn new(ctx: &'a Context, msg: &'a Message) -> Self {
         Self {
-            ctx,
-            channel_id: msg.channel_id,
+            display_mode: self.display_mode,
+            log_channel_id: self.log_channel_id,
+            macros: self.macros.clone(),
+            parent_id,
+            ..Self::from_defaults(preset)
         }
     }
 
-    /// Returns a reference to the Http of the context.
-    fn http(&self) -> &Http {
-        &self.ctx.http.as_ref()
+    /// Removes the pending challenge and returns it, if there's one that
+    /// satisfies the predicate.
+    fn take_challenge<F: FnOnce(&Challenge) -> bool>(&mut self, f: F) -> Option<Challenge> {
+        if self.challenge.as_ref().is_some_and(f) {
+            self.challenge.take()
+        } else {
+            None
+        }
     }
 
-    /// Posts a given message on the channel.
-    async fn post<T: Display>(&self, content: T) {
-        if let Err(why) = self.channel_id.say(self.http(), content).await {
-            println!("Error sending message: {:?}", why);
-        }
+    /// Returns the symbols of the players, as in `X vs O`.
+    fn players_title(&self) -> String {
+        let players: Vec<_> = self.board.players.iter().map(Player::to_string).collect();
+        players.join(" vs ")
     }
 
-    /// Gets a lock to the game configuration.
-    async fn game_config_lock(&self) -> Arc<RwLock<GameConfig>> {
-        let data_read = self.ctx.data.read().await;
-        let games_map = data_read.get::<GamesMap>().unwrap();
+    /// Describes the game in a line, to tell it apart from others in the
+    /// channel.
+    fn summary(&self) -> String {
+        format!("{}, turn {}", self.players_title(), self.board.turn + 1)
+    }
 
-        if let Some(lock) = games_map.get(self.channel_id) {
-            lock.clone()
-        } else {
-            drop(data_read);
+    /// Starts a game, and returns the message announcing it.
+    fn start(&mut self) -> String {
+        self.player_ids = vec![None; self.board.player_count()];
+        self.game_id = Uuid::new_v4();
 
-            let mut data_write = self.ctx.data.write().await;
-            data_write
-                .get_mut::<GamesMap>()
-                .unwrap()
-                .insert(self.channel_id)
-                .clone()
+        if self.bidding {
+            self.state = GameState::Bidding { bids: Vec::new() };
+            format!(
+                "{}\nBidding for the first move! {} players should send \"bid <n>\". The highest bidder moves first, but their first n moves are one character shorter.\nGame ID: {}",
+                self.board.display(self.display_mode),
+                self.board.player_count(),
+                self.game_id
+            )
+        } else {
+            self.state = GameState::Active;
+            format!(
+                "{}Game ID: {}",
+                self.board.display(self.display_mode),
+                self.game_id
+            )
         }
     }
 
-    /// Gets the game configuration and applies a function to its reference.
-    async fn game_config<Output, F: FnOnce(&GameConfig) -> Output>(&self, f: F) -> Output {
-        let game_config_lock = self.game_config_lock().await;
-        let game_config = game_config_lock.read().await;
-        f(&*game_config)
+    /// Returns whether a game is currently being played.
+    fn is_active(&self) -> bool {
+        self.state == GameState::Active
     }
 
-    /// Gets the game configuration and applies a function to its mutable reference.
-    async fn game_config_mut<Output, F: FnOnce(&mut GameConfig) -> Output>(&self, f: F) -> Output {
-        let game_config_lock = self.game_config_lock().await;
-        let mut game_config = game_config_lock.write().await;
-        f(&mut *game_config)
+    /// Returns whether the settings may currently be changed.
+    fn is_configuring(&self) -> bool {
+        self.state == GameState::Configuring
     }
-}
 
-pub struct GameHandler;
+    /// Runs a move or a bonus move on the board, and records how long it
+    /// took. Messages that aren't code at all aren't recorded.
+    fn timed_eval(&mut self, str: &str, bonus: bool) -> EvalResult<()> {
+        let turn = self.board.turn + 1;
+        let start = Instant::now();
+        let res = if bonus {
+            self.board.eval_extra(str, self.steps, &self.dialect)
+        } else {
+            self.board.eval_with(str, self.steps, &self.dialect)
+        };
 
-#[async_trait]
-impl EventHandler for GameHandler {
-    // Set a handler for the `message` event - so that whenever a new message
-    // is received - the closure (or function) passed will be called.
-    //
-    // Event handlers are dispatched through a threadpool, and so multiple
-    // events can be dispatched simultaneously.
-    async fn message(&self, ctx: Context, msg: Message) {
-        println!("Message: {}\nAuthor: {}", msg.content, msg.author.id);
-        let msg_helper = MessageHelper::new(&ctx, &msg);
+        let duration = start.elapsed();
+        let steps = match res {
+            Ok(()) => self.board.steps_used_last_move,
+            Err(EvalError::MaxSteps) => self.steps,
+            Err(EvalError::InvalidChar { .. }) => return res,
+            Err(_) => 0,
+        };
 
-        /// Posts a formatted message.
-        macro_rules! post {
-            ($($arg: tt)*) => { msg_helper.post(format!($($arg)*)).await }
-        }
+        self.perf.push(Evaluation {
+            turn,
+            duration,
+            steps,
+            max_steps: res == Err(EvalError::MaxSteps),
+        });
 
-        /// Posts a formatted message between triple backticks.
-        macro_rules! post_md {
-            ($($arg: tt)*) => { msg_helper.post(format_md!($($arg)*)).await }
-        }
+        res
+    }
 
-        /// Gets the game configuration and applies a function to its reference.
-        macro_rules! game_config {
-            ($f: expr) => {
-                msg_helper.game_config($f).await
-            };
+    /// Evaluates a Brainfuck string, and runs it. Returns `None` if inactive.
+    fn eval(&mut self, str: &str) -> Option<EvalResult<()>> {
+        let res = self.is_active().then(|| self.timed_eval(str, false))?;
+
+        self.count_failure(res);
+        Some(res)
+    }
+
+    /// Spends the current move unlocking a bucket. Returns `None` if inactive.
+    fn unlock(&mut self, position: usize) -> Option<EvalResult<()>> {
+        let res = self
+            .is_active()
+            .then(|| self.board.unlock_bucket(position))?;
+
+        self.count_failure(res);
+        Some(res)
+    }
+
+    /// Plays a bonus move. Returns `None` if inactive.
+    fn eval_bonus(&mut self, str: &str) -> Option<EvalResult<()>> {
+        let res = self.is_active().then(|| self.timed_eval(str, true))?;
+
+        if res.is_ok() {
+            self.clear_bonus();
         }
 
-        /// Gets the game configuration and applies a function to its mutable reference.
-        macro_rules! game_config_mut {
-            ($f: expr) => {
-                msg_helper.game_config_mut($f).await
-            };
+        Some(res)
+    }
+
+    /// Grants the given user a bonus move if the last move earned one.
+    /// Returns whether it did.
+    fn award_bonus(&mut self, id: UserId) -> bool {
+        if self.adjacency && self.board.adjacent_lock() {
+            self.bonus_pending = Some(id);
+            self.bonus_deadline = Some(Instant::now() + BONUS_TIMEOUT);
+            true
+        } else {
+            false
         }
+    }
 
-        // Checks for the Gamer role.
-        let has_role = match msg
-            .author
-            .has_role(&ctx.http, msg.guild_id.unwrap(), ROLE_ID)
-            .await
+    /// Returns the user who may currently play a bonus move, discarding the
+    /// bonus if it expired.
+    fn bonus(&mut self) -> Option<UserId> {
+        if self
+            .bonus_deadline
+            .is_some_and(|deadline| Instant::now() > deadline)
         {
-            // Whether the message author has the role.
-            Ok(res) => res,
+            self.clear_bonus();
+        }
 
-            // We couldn't check the role.
-            Err(err) => {
-                println!("{}", err);
-                false
-            }
+        self.bonus_pending
+    }
+
+    /// Discards any pending bonus move.
+    fn clear_bonus(&mut self) {
+        self.bonus_pending = None;
+        self.bonus_deadline = None;
+    }
+
+    /// Handles a message that isn't a command, which might be a move. Returns
+    /// the reply to post, or `None` if the message should be ignored. The
+    /// symbol of the player who moved is written to `player`. The `offset` is
+    /// the number of characters stripped from the start of the message, which
+    /// is added to the indices in errors.
+    fn play_move(
+        &mut self,
+        id: UserId,
+        name: &str,
+        content: &str,
+        offset: usize,
+        player: &mut Player,
+        outcome: &mut Option<EvalResult<()>>,
+    ) -> Option<String> {
+        let mut components = content.split_whitespace();
+        let component = components.next();
+
+        // The bucket to unlock, in case of an unlock move.
+        let unlock = if component == Some("unlock") {
+            parse_bucket(components.next())
+        } else {
+            None
         };
 
-        // Ignore messages from bots, empty messages, or people without the correct role.
-        if msg.author.bot || msg.content.chars().all(char::is_whitespace) || !has_role {
-            return;
+        *player = self.board.player();
+        let seat = self.board.player_idx();
+
+        // In case of a skip, runs the empty string as code.
+        let content = if component == Some("skip") {
+            ""
+        } else {
+            content
+        };
+
+        // Plays a pending bonus move.
+        if self.bonus() == Some(id) {
+            let res = self
+                .eval_bonus(content)?
+                .map_err(|err| err.map_indices(|idx| idx + offset));
+            if !matches!(res, Err(EvalError::InvalidChar { .. })) {
+                *outcome = Some(res);
+            }
+
+            match res {
+                Ok(()) => {
+                    let board = &self.board;
+                    *player = board.players[board.players.idx(board.turn - 1)];
+                    self.record.push(
+                        self.board.turn,
+                        *player,
+                        Action::Bonus(content.trim().to_owned()),
+                    );
+
+                    return Some(if let Some(winners) = self.board.winners() {
+                        self.end_game(winners)
+                    } else {
+                        format_md!("{}", self.board.display(self.display_mode))
+                    });
+                }
+
+                Err(EvalError::InvalidChar { .. }) => {}
+                Err(err) => return Some(format_md!("Invalid bonus move: {}.", err)),
+            }
         }
 
-        // Splits the message into tokens.
-        let mut components = msg.content.split_whitespace();
+        // Checks the message author's ID.
+        match self.id() {
+            Some(new_id) => {
+                // Ignore messages from the incorrect player.
+                if new_id != id {
+                    return None;
+                }
+            }
 
-        match components.next() {
-            // Sets up some options.
-            Some("set") => {
-                if game_config!(|cfg| cfg.active) {
-                    post_md!("Cannot configure a game while it is active!");
-                    return;
+            None => {
+                // Ignore messages from repeat users.
+                if self.player_ids.contains(&Some(id)) {
+                    return None;
                 }
+            }
+        }
 
-                match components.next() {
-                    // Setups the player characters.
-                    Some("players") => {
-                        let res = game_config_mut!(|cfg| {
-                            let mut players = Vec::new();
+        // In case of a swap, exchanges the players instead of moving.
+        if component == Some("swap") && self.can_swap() {
+            self.swap_seats(id);
+            self.record.push(self.board.turn, *player, Action::Swap);
+            *player = self.board.players[1];
 
-                            for component in components {
-                                if component.chars().count() != 1 {
+            return Some(format_md!(
+                "Players swapped! {} now plays as {}.\n{}",
+                name,
+                player,
+                self.board.display(self.display_mode)
+            ));
+        }
+
+        // Ignores everything but skips after too many failed moves
+        // this turn.
+        if self.failed_moves >= MAX_FAILED_MOVES && component != Some("skip") {
+            return None;
+        }
+
+        // Expands any macros. Since this happens before tokenizing, the full
+        // code counts towards the length limit.
+        let expanded = match self.expand_macros(content, offset) {
+            Ok(expanded) => expanded,
+            Err(err) if self.is_active() => return Some(format_md!("Invalid move: {}.", err)),
+            Err(_) => return None,
+        };
+        let content = expanded.code.as_str();
+
+        // Evaluates the message as Brainfuck code, or as an unlock.
+        let res = match unlock {
+            Some(position) if self.board.unlock => self.unlock(position),
+            _ => self.eval(content),
+        };
+
+        if let Some(res) = res {
+            // Points errors into the message as typed, rather than into the
+            // expanded code.
+            let (res, macros) = match res {
+                Ok(()) => (Ok(()), Vec::new()),
+                Err(err) => {
+                    let (err, macros) = expanded.translate(err);
+                    (Err(err), macros)
+                }
+            };
+
+            if !matches!(res, Err(EvalError::InvalidChar { .. })) {
+                *outcome = Some(res);
+            }
+
+            // Posts any error, except those by invalid moves, as
+            // they're probably just comments.
+            if let Err(err) = res {
+                if matches!(err, EvalError::InvalidChar { .. }) {
+                    None
+                } else if self.failed_moves == MAX_FAILED_MOVES {
+                    Some(format_md!(
+                        "Invalid move: {}.\nToo many failed moves, further moves other than \"skip\" will be ignored until the next turn.",
+                        fmt_move_error(err, &macros)
+                    ))
+                } else {
+                    Some(format_md!(
+                        "Invalid move: {}.",
+                        fmt_move_error(err, &macros)
+                    ))
+                }
+            }
+            // A move was succesfully made.
+            else {
+                // Seats the player, if they weren't already.
+                if let Some(seat_id) = self.player_ids.get_mut(seat) {
+                    seat_id.get_or_insert(id);
+                }
+
+                // Moving forfeits the previous player's bonus move.
+                self.clear_bonus();
+
+                // Reports how many steps the code took and what it wrote, as the
+                // game might end and reset the board below.
+                let mut usage = String::new();
+                match unlock {
+                    Some(position) if self.board.unlock => {
+                        self.record
+                            .push(self.board.turn, *player, Action::Unlock(position));
+                    }
+                    _ => self.record.push(
+                        self.board.turn,
+                        *player,
+                        Action::Code(content.trim().to_owned()),
+                    ),
+                }
+
+                if !(unlock.is_some() && self.board.unlock) {
+                    if component != Some("skip") {
+                        usage = format!(
+                            "\nLast move used {} of {} steps.",
+                            separate_thousands(self.board.steps_used_last_move),
+                            separate_thousands(self.steps)
+                        );
+                    }
+
+                    if let Some(output) = self.board.fmt_output() {
+                        write!(usage, "\nOutput: {}", output).unwrap();
+                    }
+                }
+
+                let reply =
+                    // Posts the winners.
+                    if let Some(winners) = self.board.winners() {
+                        self.end_game(winners)
+                    }
+                    // Announces the start of overtime.
+                    else if self.board.overtime_started() {
+                        format_md!(
+                            "{}\nOvertime! Next lock wins.",
+                            self.board.display(self.display_mode)
+                        )
+                    }
+                    // Announces a bonus move.
+                    else if self.award_bonus(id) {
+                        format_md!(
+                            "{}\n{} earned a bonus move, and may send a single command within {} seconds.",
+                            self.board.display(self.display_mode),
+                            name,
+                            BONUS_TIMEOUT.as_secs()
+                        )
+                    }
+                    // Posts the current state of the board, together with the poster.
+                    else if let Some(mention) = self.active_player_mention() {
+                        format!("{}\n```{}```", mention, self.board.display(self.display_mode))
+                    }
+                    // Posts the current state of the board.
+                    else {
+                        format_md!("{}", self.board.display(self.display_mode))
+                    };
+
+                // Reminds the second player that they may swap.
+                if self.can_swap() {
+                    usage += "\nThe next player may reply \"swap\" to take over this position instead of moving.";
+                }
+
+                Some(reply + &usage)
+            }
+        }
+        // The game is inactive.
+        else {
+            None
+        }
+    }
+
+    /// Announces the winners and resets the game.
+    fn end_game(&mut self, winners: Winners) -> String {
+        let reason = match self.board.winning_stretch() {
+            Some((_, buckets)) => format!(
+                " Buckets {} to {} in a row.",
+                buckets.start + 1,
+                buckets.end
+            ),
+            None if self.board.adjudicated() => {
+                format!(" The game was adjudicated after {} turns.", self.board.turn)
+            }
+            None => String::new(),
+        };
+
+        self.last_result = Some(format!("{}{}", winners, reason));
+        let mut res = format_md!(
+            "{}{} {}\n{}",
+            winners,
+            reason,
+            format_scores(&self.board),
+            self.board.display(self.display_mode)
+        );
+
+        // Credits the win to the users in the winning seats.
+        for player in winners.iter() {
+            let seat = self.board.players.iter().position(|p| p == player);
+
+            if let Some(&Some(id)) = seat.and_then(|seat| self.player_ids.get(seat)) {
+                *self.win_counts.entry(id).or_insert(0) += 1;
+            }
+        }
+
+        self.reset();
+
+        if self.pgn {
+            res += &format_md!("{}", self.last_record);
+        }
+
+        res
+    }
+
+    /// Changes the number of players, keeping the symbols of the first ones
+    /// and filling in the rest from [`DEFAULT_PLAYER_SYMBOLS`].
+    fn set_player_count(&mut self, n: u8) -> Result<(), String> {
+        let n = n as usize;
+        if n < 2 {
+            return Err("must be at least 2".to_owned());
+        }
+
+        let mut players: Vec<_> = self.board.players.iter().copied().take(n).collect();
+        for &c in DEFAULT_PLAYER_SYMBOLS.iter() {
+            if players.len() == n {
+                break;
+            }
+
+            // The default symbols are all valid.
+            let player = Player::new(c).unwrap();
+            if !players.contains(&player) {
+                players.push(player);
+            }
+        }
+
+        if players.len() < n {
+            return Err(format!("must be at most {}", DEFAULT_PLAYER_SYMBOLS.len()));
+        }
+
+        // The symbols were picked to be distinct.
+        self.board.set_players(Players::new(players).unwrap());
+        Ok(())
+    }
+
+    /// Keeps track of the failed moves in the current turn.
+    fn count_failure(&mut self, res: EvalResult<()>) {
+        match res {
+            Ok(()) => self.failed_moves = 0,
+            Err(EvalError::InvalidChar { .. }) => {}
+            Err(_) => self.failed_moves += 1,
+        }
+    }
+
+    /// Registers the bid of a user, replacing any previous bid of theirs.
+    /// Returns `None` if no bidding is taking place.
+    fn bid(&mut self, id: UserId, amount: usize) -> Option<BidOutcome> {
+        let player_count = self.board.player_count();
+        let bids = match &mut self.state {
+            GameState::Bidding { bids } => bids,
+            _ => return None,
+        };
+
+        // Once the seats are taken, as in challenges, only their users bid.
+        if self.player_ids.iter().any(Option::is_some) && !self.player_ids.contains(&Some(id)) {
+            return Some(BidOutcome::NotSeated);
+        }
+
+        if let Some(bid) = bids.iter_mut().find(|(bidder, _)| *bidder == id) {
+            bid.1 = amount;
+        } else {
+            bids.push((id, amount));
+        }
+
+        if bids.len() < player_count {
+            return Some(BidOutcome::Waiting(player_count - bids.len()));
+        }
+
+        // Sorting is stable, so equal bids keep the order they were placed in.
+        let mut bids = std::mem::take(bids);
+        bids.sort_by_key(|&(_, bid)| std::cmp::Reverse(bid));
+
+        if bids.len() > 1 && bids[0].1 == bids[1].1 {
+            return Some(BidOutcome::Tie);
+        }
+
+        // Seats the players in bid order, starting from whoever moves first.
+        let first = self.board.player_idx();
+        for (i, &(bidder, _)) in bids.iter().enumerate() {
+            self.player_ids[(first + i) % player_count] = Some(bidder);
+        }
+
+        self.board.set_handicap(first, bids[0].1);
+        if bids[0].1 != 0 {
+            self.record.push(
+                self.board.turn + 1,
+                self.board.player(),
+                Action::Handicap(bids[0].1),
+            );
+        }
+
+        self.state = GameState::Active;
+        Some(BidOutcome::Done(bids[0].0))
+    }
+
+    /// Resets the game configuration to what it was before the game started.
+    fn reset(&mut self) {
+        if self.swapped {
+            self.board.players.swap(0, 1);
+            self.swapped = false;
+        }
+
+        self.state = GameState::Configuring;
+        self.failed_moves = 0;
+        self.clear_bonus();
+        self.board_message = None;
+        self.player_ids = Vec::new();
+
+        if !self.record.is_empty() {
+            self.last_record = std::mem::take(&mut self.record);
+        }
+
+        self.board.reset();
+    }
+
+    /// Describes the current settings of the game.
+    fn settings(&self) -> String {
+        let board = &self.board;
+        let mut res = String::new();
+
+        let players: Vec<_> = board.players.iter().map(Player::to_string).collect();
+        let (first, second) = board.tape_capacities();
+        let capacities: Vec<_> = first.iter().map(usize::to_string).collect();
+        let second: Vec<_> = second.iter().map(usize::to_string).collect();
+        let start: Vec<_> = board
+            .start
+            .iter()
+            .map(|(position, counters)| {
+                let counters: String = counters.iter().map(Player::to_string).collect();
+                format!("{}:{}", position + 1, counters)
+            })
+            .collect();
+
+        writeln!(res, "Players: {}", players.join(" ")).unwrap();
+        writeln!(res, "Board: {}", capacities.join(" ")).unwrap();
+
+        if second.is_empty() {
+            writeln!(res, "Second tape: none").unwrap();
+        } else {
+            writeln!(res, "Second tape: {}", second.join(" ")).unwrap();
+        }
+
+        writeln!(res, "Layout: {}", board.layout).unwrap();
+
+        writeln!(res, "Buffer buckets: {}", board.buffer_buckets).unwrap();
+
+        if start.is_empty() {
+            writeln!(res, "Starting position: none").unwrap();
+        } else {
+            writeln!(res, "Starting position: {}", start.join(" ")).unwrap();
+        }
+
+        match board.supply {
+            Some(supply) => writeln!(res, "Supply: {} counters", supply).unwrap(),
+            None => writeln!(res, "Supply: unlimited").unwrap(),
+        }
+
+        match board.max_turns {
+            Some(turns) => writeln!(res, "Turn limit: {} turns", turns).unwrap(),
+            None => writeln!(res, "Turn limit: none").unwrap(),
+        }
+
+        match board.opening_no_brackets {
+            0 => writeln!(res, "Opening restriction: none").unwrap(),
+            turns => writeln!(res, "Opening restriction: no loops for {} turns", turns).unwrap(),
+        }
+
+        match board.decrement_limit {
+            Some(limit) => writeln!(res, "Decrement limit: {}", limit).unwrap(),
+            None => writeln!(res, "Decrement limit: none").unwrap(),
+        }
+
+        writeln!(res, "Steps: {}", self.steps).unwrap();
+        writeln!(res, "Charset: {}", self.dialect.charset).unwrap();
+        writeln!(res, "Extensions: {}", on_off(self.dialect.extensions)).unwrap();
+        writeln!(res, "I/O: {}", on_off(self.dialect.io)).unwrap();
+        writeln!(res, "Maximum loop nesting: {}", self.dialect.max_nesting).unwrap();
+        writeln!(res, "Swap rule: {}", on_off(self.swap)).unwrap();
+        writeln!(res, "Bidding: {}", on_off(self.bidding)).unwrap();
+        writeln!(res, "Lock rule: {}", board.lock_rule).unwrap();
+        writeln!(res, "Win rule: {}", board.win_rule).unwrap();
+        writeln!(res, "Tie-break: {}", board.tie_break).unwrap();
+        writeln!(res, "Capture rule: {}", on_off(board.capture)).unwrap();
+        writeln!(res, "Unlocking: {}", on_off(board.unlock)).unwrap();
+        writeln!(res, "Decay: {}", on_off(board.decay)).unwrap();
+        writeln!(res, "Gravity: {}", on_off(board.gravity)).unwrap();
+
+        match board.shrink {
+            Some(rounds) => writeln!(res, "Shrinking: every {} rounds", rounds).unwrap(),
+            None => writeln!(res, "Shrinking: off").unwrap(),
+        }
+
+        writeln!(res, "Overtime: {}", on_off(board.overtime)).unwrap();
+        writeln!(res, "Display: {}", self.display_mode).unwrap();
+        writeln!(res, "Profiling: {}", on_off(board.profiling)).unwrap();
+
+        match self.log_channel_id {
+            Some(id) => writeln!(res, "Log channel: {}", id).unwrap(),
+            None => writeln!(res, "Log channel: none").unwrap(),
+        }
+        writeln!(res, "Adjacency bonus: {}", on_off(self.adjacency)).unwrap();
+        writeln!(res, "Notation: {}", if self.pgn { "pgn" } else { "off" }).unwrap();
+        write!(res, "Komi: {}", board.komi).unwrap();
+        res
+    }
+
+    /// Returns whether the second player may currently swap places with the
+    /// first, which is only possible right after the first move.
+    fn can_swap(&self) -> bool {
+        self.is_active() && self.swap && self.board.turn == 1 && self.board.player_count() == 2
+    }
+
+    /// Exchanges the symbols of the two players, seating the given user as the
+    /// second player.
+    fn swap_seats(&mut self, id: UserId) {
+        self.board.swap_first_players();
+        self.player_ids[1] = Some(id);
+        self.swapped = true;
+        self.failed_moves = 0;
+    }
+
+    /// Returns the session stats, listing every user who won a game by their
+    /// number of wins.
+    fn stats(&self) -> String {
+        if self.win_counts.is_empty() {
+            return "No games have been won yet!".to_owned();
+        }
+
+        let mut counts: Vec<_> = self.win_counts.iter().collect();
+        counts.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+        let counts: Vec<_> = counts
+            .into_iter()
+            .map(|(id, &wins)| {
+                let s = if wins == 1 { "" } else { "s" };
+                format!("<@{}> {} win{}", id, wins, s)
+            })
+            .collect();
+
+        format!("Session stats: {}", counts.join(", "))
+    }
+
+    /// Gets the user ID of the current player, or `None` if it hasn't yet been set.
+    fn id(&self) -> Option<UserId> {
+        self.player_ids
+            .get(self.board.player_idx())
+            .copied()
+            .flatten()
+    }
+
+    /// Replaces every `$name` in a move by the code of the macro with that
+    /// name. A `$` not followed by a letter or digit is kept as is. Indices
+    /// into the move are shifted by the given offset.
+    fn expand_macros(&self, content: &str, offset: usize) -> Result<Expansion, String> {
+        let mut res = Expansion::default();
+        let mut chars = content.chars().enumerate().peekable();
+
+        while let Some((idx, c)) = chars.next() {
+            let idx = idx + offset;
+            if c != '$' {
+                res.push(c, idx);
+                continue;
+            }
+
+            let mut name = String::new();
+            while let Some(&(_, c)) = chars.peek() {
+                if !is_macro_char(c) {
+                    break;
+                }
+
+                name.push(c);
+                chars.next();
+            }
+
+            if name.is_empty() {
+                res.push('$', idx);
+            } else if let Some(code) = self.macros.get(&name) {
+                for c in code.chars() {
+                    res.push(c, idx);
+                }
+                res.names.insert(idx, name);
+            } else {
+                return Err(format!("unknown macro ${} at index {}", name, idx + 1));
+            }
+        }
+
+        Ok(res)
+    }
+
+    /// Mentions the current player, or returns `None` if their user ID hasn't
+    /// yet been set.
+    fn active_player_mention(&self) -> Option<String> {
+        self.id().map(|id| format!("<@{}>", id))
+    }
+
+    /// Names the player to move, such as "Player X", or returns "Waiting" if
+    /// no game is active.
+    fn active_player_display(&self) -> String {
+        if self.is_active() {
+            format!("Player {}", self.board.player())
+        } else {
+            "Waiting".to_owned()
+        }
+    }
+}
+
+/// A move with its macros expanded, which remembers where each character of
+/// the code was typed.
+#[derive(Default)]
+struct Expansion {
+    /// The expanded code.
+    code: String,
+
+    /// The index in the move of each character of the code. Characters from
+    /// a macro point to the `$` it was invoked with.
+    origins: Vec<usize>,
+
+    /// The name of each macro invoked, by the index of its `$`.
+    names: BTreeMap<usize, String>,
+}
+
+impl Expansion {
+    /// Appends a character of the code, typed at the given index.
+    fn push(&mut self, c: char, idx: usize) {
+        self.code.push(c);
+        self.origins.push(idx);
+    }
+
+    /// Points the indices of an error into the move as it was typed. Also
+    /// returns the names of the macros these indices fell in, if any.
+    fn translate(&self, err: EvalError) -> (EvalError, Vec<&str>) {
+        let mut names = Vec::new();
+        let err = err.map_indices(|idx| {
+            let origin = self.origins.get(idx).copied().unwrap_or(idx);
+            if let Some(name) = self.names.get(&origin) {
+                if !names.contains(&name.as_str()) {
+                    names.push(name.as_str());
+                }
+            }
+
+            origin
+        });
+
+        (err, names)
+    }
+}
+
+/// Formats an error from a move, naming the macros it came from.
+fn fmt_move_error(err: EvalError, macros: &[&str]) -> String {
+    match macros {
+        [] => err.to_string(),
+        [name] => format!("{}, in macro ${}", err, name),
+        _ => format!(
+            "{}, in macros {}",
+            err,
+            macros
+                .iter()
+                .map(|name| format!("${}", name))
+                .collect::<Vec<_>>()
+                .join(" and ")
+        ),
+    }
+}
+
+/// The result of placing a bid.
+enum BidOutcome {
+    /// The given number of bids are still missing.
+    Waiting(usize),
+
+    /// The highest bids were tied, so every player must bid again.
+    Tie,
+
+    /// The bidding is over, and the given user moves first.
+    Done(UserId),
+
+    /// The user isn't seated in the game, whose seats were taken beforehand.
+    NotSeated,
+}
+
+/// An invitation from a user to another to play a two-player game.
+#[derive(Debug)]
+struct Challenge {
+    /// The user who sent the challenge, and moves first.
+    challenger: UserId,
+
+    /// The user who was challenged.
+    target: UserId,
+
+    /// The settings the game is played with, if not those of the channel.
+    preset: Option<Defaults>,
+
+    /// When the challenge expires.
+    deadline: Instant,
+}
+
+impl Challenge {
+    /// Returns whether the challenge went unanswered for too long.
+    fn expired(&self) -> bool {
+        Instant::now() > self.deadline
+    }
+}
+
+/// A helper struct whose associated methods wrap around some common operations.
+struct MessageHelper<'a> {
+    /// The context used to send messages.
+    ctx: &'a Context,
+
+    /// The ID of the channel in which messages are sent.
+    channel_id: ChannelId,
+
+    /// The ID of the guild the channel is in, if any.
+    guild_id: Option<GuildId>,
+
+    /// The short ID of the game within the channel that's acted on.
+    game: u32,
+}
+
+impl<'a> MessageHelper<'a> {
+    /// Initializes a new message helper.
+    fn new(ctx: &'a Context, msg: &'a Message) -> Self {
+        Self::from_channel(ctx, msg.channel_id, msg.guild_id)
+    }
+
+    /// Initializes a new message helper for the given channel.
+    fn from_channel(ctx: &'a Context, channel_id: ChannelId, guild_id: Option<GuildId>) -> Self {
+        Self {
+            ctx,
+            channel_id,
+            guild_id,
+            game: PRIMARY_GAME,
+        }
+    }
+
+    /// Returns a message helper for another game in the same channel.
+    fn with_game(&self, game: u32) -> Self {
+        Self {
+            game,
+            ..Self::from_channel(self.ctx, self.channel_id, self.guild_id)
+        }
+    }
+
+    /// Returns a reference to the Http of the context.
+    fn http(&self) -> &Http {
+        &self.ctx.http.as_ref()
+    }
+
+    /// Echoes a line to the log channel of the game, if it has one. The line
+    /// is sent in the background, so that it doesn't hold up the game.
+    async fn log<T: Display>(&self, line: T) {
+        let (log_channel_id, game_id) = match self
+            .game_config(|cfg| cfg.log_channel_id.map(|id| (id, cfg.game_id)))
+            .await
+        {
+            Some(ids) => ids,
+            None => return,
+        };
+
+        let http = self.ctx.http.clone();
+        let line = if game_id.is_nil() {
+            format!("[<#{}>] {}", self.channel_id, line)
+        } else {
+            format!("[<#{}>] [{}] {}", self.channel_id, game_id, line)
+        };
+
+        tokio::spawn(async move {
+            if let Err(why) = log_channel_id.say(&*http, line).await {
+                println!("Error logging to channel: {:?}", why);
+            }
+        });
+    }
+
+    /// Logs a move, together with whether it succeeded.
+    async fn log_move(
+        &self,
+        name: &str,
+        player: Player,
+        code: &str,
+        turn: u32,
+        outcome: EvalResult<()>,
+    ) {
+        let result = match outcome {
+            Ok(()) => "OK".to_owned(),
+            Err(err) => err.to_string(),
+        };
+
+        self.log(format!(
+            "@{} (Player {}) submitted \"{}\" on turn {} → {}",
+            name,
+            player,
+            code.trim(),
+            turn + 1,
+            result
+        ))
+        .await;
+    }
+
+    /// Posts a given message on the channel.
+    async fn post<T: Display>(&self, content: T) {
+        self.post_message(content).await;
+    }
+
+    /// Posts a given message on the channel, and returns it if successful.
+    async fn post_message<T: Display>(&self, content: T) -> Option<Message> {
+        match self.channel_id.say(self.http(), content).await {
+            Ok(message) => Some(message),
+            Err(why) => {
+                println!("Error sending message: {:?}", why);
+                None
+            }
+        }
+    }
+
+    /// Posts the reply to a move, and updates the nickname of the user who
+    /// made it. If the turn passed, reactions are added so that the next
+    /// player can move with them.
+    async fn post_move(
+        &self,
+        post: String,
+        moved: bool,
+        id: UserId,
+        player: Player,
+        guild_id: Option<GuildId>,
+    ) {
+        if let Some(message) = self.post_message(post).await {
+            if moved && self.game_config(|cfg| cfg.is_active()).await {
+                self.game_config_mut(|cfg| cfg.board_message = Some(message.id))
+                    .await;
+
+                for (emoji, _) in REACTION_MOVES.iter() {
+                    let reaction = ReactionType::Unicode(emoji.to_string());
+                    if let Err(why) = message.react(self.http(), reaction).await {
+                        println!("Error adding reaction: {:?}", why);
+                    }
+                }
+            }
+        }
+
+        self.finish_game().await;
+
+        guild_id
+            .unwrap()
+            .edit_member(self.http(), id, |m| m.nickname(player.to_string()))
+            .await
+            .unwrap();
+    }
+
+    /// Returns whether the author of the message has the admin role.
+    async fn is_admin(&self, msg: &Message) -> bool {
+        let (role_id, guild_id) = match (admin_role_id(), msg.guild_id) {
+            (Some(role_id), Some(guild_id)) => (role_id, guild_id),
+            _ => return false,
+        };
+
+        match msg.author.has_role(self.ctx, guild_id, role_id).await {
+            Ok(res) => res,
+            Err(err) => {
+                println!("{}", err);
+                false
+            }
+        }
+    }
+
+    /// Returns whether the user has the Gamer role, which is needed to play.
+    async fn is_gamer(&self, user: &User) -> bool {
+        let guild_id = match self.guild_id {
+            Some(guild_id) => guild_id,
+            None => return false,
+        };
+
+        match user.has_role(self.ctx, guild_id, ROLE_ID).await {
+            // Whether the user has the role.
+            Ok(res) => res,
+
+            // We couldn't check the role.
+            Err(err) => {
+                println!("{}", err);
+                false
+            }
+        }
+    }
+
+    /// Gets the games of every channel.
+    async fn games_map(&self) -> Arc<GamesMap> {
+        self.ctx
+            .data
+            .read()
+            .await
+            .get::<GamesMap>()
+            .unwrap()
+            .clone()
+    }
+
+    /// Gets the list of bundled puzzles.
+    async fn puzzles(&self) -> Arc<Vec<Puzzle>> {
+        self.ctx.data.read().await.get::<Puzzles>().unwrap().clone()
+    }
+
+    /// Gets the timing statistics over every channel.
+    async fn perf_counters(&self) -> Arc<PerfCounters> {
+        self.ctx.data.read().await.get::<Perf>().unwrap().clone()
+    }
+
+    /// Gets the default settings of the guild, if it has any.
+    async fn guild_defaults(&self) -> Option<Defaults> {
+        let guild_id = self.guild_id?;
+        let data_read = self.ctx.data.read().await;
+        data_read
+            .get::<GuildDefaults>()
+            .unwrap()
+            .get(&guild_id)
+            .cloned()
+    }
+
+    /// Replaces the default settings of the guild, or removes them if `None`
+    /// is passed, both in memory and on disk.
+    async fn set_guild_defaults(&self, defaults: Option<Defaults>) -> std::io::Result<()> {
+        let guild_id = match self.guild_id {
+            Some(guild_id) => guild_id,
+            None => return Ok(()),
+        };
+
+        match &defaults {
+            Some(defaults) => storage::save(defaults::KIND, guild_id, defaults)?,
+            None => storage::clear(defaults::KIND, guild_id)?,
+        }
+
+        let mut data_write = self.ctx.data.write().await;
+        let map = data_write.get_mut::<GuildDefaults>().unwrap();
+        match defaults {
+            Some(defaults) => map.insert(guild_id, defaults),
+            None => map.remove(&guild_id),
+        };
+
+        Ok(())
+    }
+
+    /// Returns whether the bot plays in the channel, which is always the case
+    /// unless its guild has an allowlist.
+    async fn is_allowed(&self) -> bool {
+        let guild_id = match self.guild_id {
+            Some(guild_id) => guild_id,
+            None => return true,
+        };
+
+        // Threads holding a game are allowed whenever the channel they were
+        // started from is.
+        let lock = self.games_map().await.get(self.channel_id, PRIMARY_GAME);
+
+        let channel_id = match lock {
+            Some(lock) => lock.read().await.parent_id.unwrap_or(self.channel_id),
+            None => self.channel_id,
+        };
+
+        let data_read = self.ctx.data.read().await;
+        data_read
+            .get::<ChannelAllowlists>()
+            .unwrap()
+            .get(&guild_id)
+            .is_none_or(|channels| channels.contains(&channel_id))
+    }
+
+    /// Gets the allowlist of the guild, if it has one.
+    async fn allowlist(&self) -> Option<BTreeSet<ChannelId>> {
+        let guild_id = self.guild_id?;
+        let data_read = self.ctx.data.read().await;
+        data_read
+            .get::<ChannelAllowlists>()
+            .unwrap()
+            .get(&guild_id)
+            .cloned()
+    }
+
+    /// Adds a channel to the allowlist of the guild, or removes it from it,
+    /// both in memory and on disk. Once the last channel is removed, the bot
+    /// plays anywhere again.
+    async fn update_allowlist(&self, channel_id: ChannelId, add: bool) -> std::io::Result<()> {
+        let guild_id = match self.guild_id {
+            Some(guild_id) => guild_id,
+            None => return Ok(()),
+        };
+
+        let mut data_write = self.ctx.data.write().await;
+        let allowlists = data_write.get_mut::<ChannelAllowlists>().unwrap();
+        let mut channels = allowlists.get(&guild_id).cloned().unwrap_or_default();
+
+        if add {
+            channels.insert(channel_id);
+        } else {
+            channels.remove(&channel_id);
+        }
+
+        if channels.is_empty() {
+            storage::clear(ALLOWLIST_KIND, guild_id)?;
+            allowlists.remove(&guild_id);
+        } else {
+            storage::save(ALLOWLIST_KIND, guild_id, &channels)?;
+            allowlists.insert(guild_id, channels);
+        }
+
+        Ok(())
+    }
+
+    /// Starts a game with the settings of the channel, or with the given
+    /// preset, and applies `setup` to it once started. The game is played in
+    /// a new thread off the message if possible, and otherwise in the
+    /// channel, alongside its game if it's busy or a preset is used. Returns
+    /// a helper for the game together with the reply announcing it, or the
+    /// reply to post if it couldn't start.
+    async fn start_game<F: Fn(&mut GameConfig)>(
+        &self,
+        msg: &Message,
+        preset: Option<Defaults>,
+        setup: F,
+    ) -> Result<(Self, String), String> {
+        if let Some(thread_id) = self.start_in_thread(msg, preset.clone(), &setup).await {
+            return Ok((
+                MessageHelper::from_channel(self.ctx, thread_id, self.guild_id),
+                format!("Game started in <#{}>!", thread_id),
+            ));
+        }
+
+        if preset.is_none() {
+            let board = self
+                .game_config_mut(|cfg| {
+                    if !cfg.is_configuring() {
+                        return None;
+                    }
+
+                    let board = cfg.start();
+                    setup(cfg);
+                    Some(board)
+                })
+                .await;
+
+            if let Some(board) = board {
+                return Ok((self.with_game(PRIMARY_GAME), format_md!("{}", board)));
+            }
+        }
+
+        // The channel's game is busy, so this one is played alongside it.
+        let (game, board) = self
+            .start_extra_game(preset, &setup)
+            .await
+            .map_err(|err| format_md!("{}", err))?;
+
+        Ok((
+            self.with_game(game),
+            format_md!(
+                "Game #{} started! Prefix your moves with #{} if you play in more than one game.\n{}",
+                game,
+                game,
+                board
+            ),
+        ))
+    }
+
+    /// Starts a game in a new thread off the message, with the settings of
+    /// the channel or the given preset, and returns the ID of the thread.
+    /// Nothing happens if the thread can't be created.
+    async fn start_in_thread<F: Fn(&mut GameConfig)>(
+        &self,
+        msg: &Message,
+        preset: Option<Defaults>,
+        setup: F,
+    ) -> Option<ChannelId> {
+        let guild_id = self.guild_id?;
+        let (name, mut config) = self
+            .game_config(|cfg| {
+                let config = cfg.copy_settings(Some(self.channel_id), preset);
+                let name = format!(
+                    "{} ({})",
+                    config.players_title(),
+                    msg.timestamp.format("%Y-%m-%d")
+                );
+
+                (name, config)
+            })
+            .await;
+
+        let thread = match self
+            .channel_id
+            .create_public_thread(self.http(), msg.id, |t| t.name(name))
+            .await
+        {
+            Ok(thread) => thread,
+            Err(why) => {
+                println!("Error creating thread: {:?}", why);
+                return None;
+            }
+        };
+
+        let start = config.start();
+        setup(&mut config);
+        self.games_map()
+            .await
+            .get_or_insert_with(thread.id, || config);
+
+        MessageHelper::from_channel(self.ctx, thread.id, Some(guild_id))
+            .post(format_md!("{}", start))
+            .await;
+        Some(thread.id)
+    }
+
+    /// Starts a game alongside the primary one, with its settings or the
+    /// given preset, and returns the short ID of the new game together with
+    /// the message announcing it. Threads only ever hold one game.
+    async fn start_extra_game<F: Fn(&mut GameConfig)>(
+        &self,
+        preset: Option<Defaults>,
+        setup: F,
+    ) -> Result<(u32, String), &'static str> {
+        let mut config = self
+            .game_config(|cfg| match cfg.parent_id {
+                Some(_) => None,
+                None => Some(cfg.copy_settings(None, preset)),
+            })
+            .await
+            .ok_or("A game is already active!")?;
+
+        let start = config.start();
+        setup(&mut config);
+        let game = self
+            .games_map()
+            .await
+            .insert(self.channel_id, config, MAX_GAMES)
+            .ok_or("Too many games are active in this channel!")?;
+        Ok((game, start))
+    }
+
+    /// Returns the games in the channel, by short ID.
+    async fn games(&self) -> Vec<(u32, Arc<RwLock<GameConfig>>)> {
+        self.games_map().await.games(self.channel_id)
+    }
+
+    /// Picks the game a command is meant for, given the short ID the user
+    /// named, if any. Otherwise, the one game that passes the filter is
+    /// picked, or the primary one if none does. If several do, the reply
+    /// listing them is returned instead.
+    async fn select_game<F: Fn(&GameConfig) -> bool>(
+        &self,
+        component: Option<&str>,
+        filter: F,
+    ) -> Result<Self, String> {
+        let games = self.games().await;
+
+        if let Some(component) = component {
+            return match parse_game(component) {
+                Some(game) if games.iter().any(|&(n, _)| n == game) => Ok(self.with_game(game)),
+                Some(game) => Err(format!("There's no game #{} in this channel!", game)),
+                None => Err("Game could not be parsed.".to_owned()),
+            };
+        }
+
+        let mut matches = Vec::new();
+        for (game, lock) in games {
+            let cfg = lock.read().await;
+            if filter(&cfg) {
+                matches.push((game, format!("#{}: {}", game, cfg.summary())));
+            }
+        }
+
+        match matches[..] {
+            [] => Ok(self.with_game(PRIMARY_GAME)),
+            [(game, _)] => Ok(self.with_game(game)),
+            _ => {
+                let list: Vec<_> = matches.into_iter().map(|(_, line)| line).collect();
+                Err(format!(
+                    "Several games are active. Specify one of them:\n{}",
+                    list.join("\n")
+                ))
+            }
+        }
+    }
+
+    /// Picks the game a move is meant for, and returns a helper for it along
+    /// with the move itself. Moves may name their game with a prefix like
+    /// `#2`, and skips may name it afterwards. Otherwise, the game is guessed
+    /// from where it's the user's turn, then from where they're seated, and
+    /// then from where the current seat is free. When that isn't enough, the
+    /// reply to post is returned instead, if the message looks like a move.
+    /// The number of characters stripped from the start of the message is
+    /// returned too, so that errors point into the message as typed.
+    async fn route_move<'b>(
+        &self,
+        user: UserId,
+        content: &'b str,
+    ) -> Result<(Self, &'b str, usize), Option<String>> {
+        let games = self.games().await;
+
+        if let Some((game, content, offset)) = parse_named_move(content) {
+            return if games.iter().any(|&(n, _)| n == game) {
+                Ok((self.with_game(game), content, offset))
+            } else {
+                Err(Some(format_md!(
+                    "There's no game #{} in this channel!",
+                    game
+                )))
+            };
+        }
+
+        // The active games, whether it's the user's turn or they're seated
+        // in them, whether the current seat is free, and their summaries.
+        let mut active = Vec::new();
+        let mut dialect = Dialect::default();
+        for (game, lock) in games {
+            let cfg = lock.read().await;
+            if game == PRIMARY_GAME {
+                dialect = cfg.dialect.clone();
+            }
+
+            if cfg.is_active() {
+                let seat = cfg.player_ids.get(cfg.board.player_idx()).copied();
+                active.push((
+                    game,
+                    seat == Some(Some(user)),
+                    cfg.player_ids.contains(&Some(user)),
+                    seat == Some(None),
+                    cfg.summary(),
+                ));
+            }
+        }
+
+        if active.len() <= 1 {
+            let game = active.first().map_or(PRIMARY_GAME, |g| g.0);
+            return Ok((self.with_game(game), content, 0));
+        }
+
+        let their_turn: Vec<_> = active.iter().filter(|g| g.1).map(|g| g.0).collect();
+        let seated: Vec<_> = active.iter().filter(|g| g.2).map(|g| g.0).collect();
+        let free: Vec<_> = active.iter().filter(|g| g.3).map(|g| g.0).collect();
+        let candidates = if !their_turn.is_empty() {
+            their_turn
+        } else if !seated.is_empty() {
+            seated
+        } else {
+            free
+        };
+
+        if let [game] = candidates[..] {
+            return Ok((self.with_game(game), content, 0));
+        }
+
+        // Ordinary conversation is ignored.
+        let trimmed = content.trim();
+        if trimmed != "skip"
+            && !trimmed.starts_with("unlock")
+            && Brainfuck::with_dialect(trimmed, &dialect).is_err()
+        {
+            return Err(None);
+        }
+
+        let list: Vec<_> = active
+            .iter()
+            .map(|g| format!("#{}: {}", g.0, g.4))
+            .collect();
+        Err(Some(format_md!(
+            "Several games are active, so prefix your move with the game it's for, as in \"#{} {}\":\n{}",
+            active[0].0,
+            trimmed,
+            list.join("\n")
+        )))
+    }
+
+    /// Wraps up a game once it's over, unless it's the primary game of a
+    /// channel. Its wins are credited to the primary game of the channel it
+    /// was started from, and it's removed. Games played in a thread also
+    /// have their result posted in that channel, and the thread is archived.
+    async fn finish_game(&self) {
+        let (parent_id, result, win_counts) = match self
+            .game_config_mut(|cfg| {
+                if cfg.is_configuring() && (cfg.parent_id.is_some() || self.game != PRIMARY_GAME) {
+                    Some((
+                        cfg.parent_id,
+                        cfg.last_result.take(),
+                        std::mem::take(&mut cfg.win_counts),
+                    ))
+                } else {
+                    None
+                }
+            })
+            .await
+        {
+            Some(res) => res,
+            None => return,
+        };
+
+        let games_map = self.games_map().await;
+        match parent_id {
+            Some(_) => games_map.remove(self.channel_id),
+            None => games_map.remove_game(self.channel_id, self.game),
+        }
+
+        let primary = MessageHelper::from_channel(
+            self.ctx,
+            parent_id.unwrap_or(self.channel_id),
+            self.guild_id,
+        );
+        primary
+            .game_config_mut(|cfg| {
+                for (id, wins) in win_counts {
+                    *cfg.win_counts.entry(id).or_insert(0) += wins;
+                }
+            })
+            .await;
+
+        // Only games played in a thread post their result elsewhere.
+        if parent_id.is_none() {
+            return;
+        }
+
+        primary
+            .post(match result {
+                Some(result) => format!("Game in <#{}> ended. {}", self.channel_id, result),
+                None => format!("Game in <#{}> was reset.", self.channel_id),
+            })
+            .await;
+
+        if let Err(why) = self
+            .channel_id
+            .edit_thread(self.http(), |t| t.archived(true))
+            .await
+        {
+            println!("Error archiving thread: {:?}", why);
+        }
+    }
+
+    /// Adds an evaluation to the global counters.
+    async fn report_evaluation(&self, evaluation: Option<Evaluation>) {
+        if let Some(evaluation) = evaluation {
+            self.perf_counters().await.record(&evaluation);
+        }
+    }
+
+    /// Gets a lock to the game configuration.
+    async fn game_config_lock(&self) -> Arc<RwLock<GameConfig>> {
+        let games_map = self.games_map().await;
+
+        if let Some(lock) = games_map.get(self.channel_id, self.game) {
+            lock
+        } else if self.game != PRIMARY_GAME {
+            // The game ended in the meantime, so a throwaway configuration
+            // is returned, as there's nothing left to act on.
+            Arc::new(RwLock::new(GameConfig::default()))
+        } else {
+            // Channels off the allowlist only get this far through commands
+            // that work anywhere, and aren't given a configuration to keep.
+            if !self.is_allowed().await {
+                return Arc::new(RwLock::new(GameConfig::default()));
+            }
+
+            // Another message might have inserted the configuration in the
+            // meantime, in which case it's reused. New channels start with the
+            // defaults of their guild.
+            let defaults = self.guild_defaults().await;
+            games_map.get_or_insert_with(self.channel_id, || {
+                defaults.map_or_else(GameConfig::default, GameConfig::from_defaults)
+            })
+        }
+    }
+
+    /// Gets the game configuration and applies a function to its reference.
+    async fn game_config<Output, F: FnOnce(&GameConfig) -> Output>(&self, f: F) -> Output {
+        let game_config_lock = self.game_config_lock().await;
+        let game_config = game_config_lock.read().await;
+        f(&*game_config)
+    }
+
+    /// Gets the game configuration and applies a function to its mutable reference.
+    async fn game_config_mut<Output, F: FnOnce(&mut GameConfig) -> Output>(&self, f: F) -> Output {
+        let game_config_lock = self.game_config_lock().await;
+        let mut game_config = game_config_lock.write().await;
+        f(&mut *game_config)
+    }
+}
+
+pub struct GameHandler;
+
+#[async_trait]
+impl EventHandler for GameHandler {
+    // Set a handler for the `message` event - so that whenever a new message
+    // is received - the closure (or function) passed will be called.
+    //
+    // Event handlers are dispatched through a threadpool, and so multiple
+    // events can be dispatched simultaneously.
+    async fn message(&self, ctx: Context, msg: Message) {
+        println!("Message: {}\nAuthor: {}", msg.content, msg.author.id);
+        let msg_helper = MessageHelper::new(&ctx, &msg);
+
+        /// Posts a formatted message.
+        macro_rules! post {
+            ($($arg: tt)*) => { msg_helper.post(format!($($arg)*)).await }
+        }
+
+        /// Posts a formatted message between triple backticks.
+        macro_rules! post_md {
+            ($($arg: tt)*) => { msg_helper.post(format_md!($($arg)*)).await }
+        }
+
+        /// Gets the game configuration and applies a function to its reference.
+        macro_rules! game_config {
+            ($f: expr) => {
+                msg_helper.game_config($f).await
+            };
+        }
+
+        /// Gets the game configuration and applies a function to its mutable reference.
+        macro_rules! game_config_mut {
+            ($f: expr) => {
+                msg_helper.game_config_mut($f).await
+            };
+        }
+
+        // Ignore messages from bots, empty messages, or people without the correct role.
+        if msg.author.bot
+            || msg.content.chars().all(char::is_whitespace)
+            || !msg_helper.is_gamer(&msg.author).await
+        {
+            return;
+        }
+
+        // Splits the message into tokens.
+        let mut components = msg.content.split_whitespace();
+
+        // Ignores channels off the allowlist, except to manage it.
+        if components.clone().next() != Some("bfchannel") && !msg_helper.is_allowed().await {
+            return;
+        }
+
+        // Logs any command.
+        if components
+            .clone()
+            .next()
+            .is_some_and(|command| COMMANDS.contains(&command))
+        {
+            msg_helper
+                .log(format!(
+                    "@{} ran \"{}\"",
+                    msg.author.name,
+                    msg.content.trim()
+                ))
+                .await;
+        }
+
+        match components.next() {
+            // Sets up some options.
+            Some("set") => {
+                if !game_config!(|cfg| cfg.is_configuring()) {
+                    post_md!("{}", SETTINGS_LOCKED);
+                    return;
+                }
+
+                match components.next() {
+                    // Setups the player characters.
+                    Some("players") => {
+                        let res = game_config_mut!(|cfg| {
+                            if !cfg.is_configuring() {
+                                return SETTINGS_LOCKED.to_owned();
+                            }
+
+                            let components: Vec<_> = components.collect();
+
+                            // A single number sets the player count.
+                            if let [count] = components[..] {
+                                if let Ok(n) = count.parse() {
+                                    return match cfg.set_player_count(n) {
+                                        Ok(()) => {
+                                            let players: Vec<_> = cfg
+                                                .board
+                                                .players
+                                                .iter()
+                                                .map(Player::to_string)
+                                                .collect();
+                                            format!(
+                                                "Players succesfully updated! Now playing as {}.",
+                                                players.join(" ")
+                                            )
+                                        }
+                                        Err(err) => {
+                                            format!("Players could not be updated: {}.", err)
+                                        }
+                                    };
+                                }
+                            }
+
+                            let mut players = Vec::new();
+
+                            for component in components {
+                                if component.chars().count() != 1 {
                                     return "Each player must be represented by a single character!"
                                     .to_owned();
+                                }
+
+                                let player = match Player::new(component.chars().next().unwrap()) {
+                                    Ok(player) => player,
+                                    Err(err) => {
+                                        return format!("Players could not be updated: {}.", err)
+                                    }
+                                };
+
+                                if player.is_neutral() {
+                                    return format!("Players could not be updated: {} is reserved for neutral counters.", player);
+                                }
+
+                                players.push(player);
+                            }
+
+                            match players.len() {
+                                0 => "Configure the players. Specify the characters that will be used to represent each player as a list separated by spaces, or just the number of players.".to_owned(),
+                                1 => "Players could not be updated: must be at least 2.".to_owned(),
+                                _ => match Players::new(players) {
+                                    Ok(players) => {
+                                        cfg.board.set_players(players);
+                                        "Players succesfully updated!".to_owned()
+                                    }
+                                    Err(err) => format!("Players could not be updated: {}.", err),
+                                },
+                            }
+                        });
+
+                        post_md!("{}", res);
+                    }
+
+                    // Setups the maximum number of steps any instruction runs for.
+                    Some("steps") => {
+                        if let Some(component) = components.next() {
+                            if let Ok(steps) = component.parse::<u32>() {
+                                if steps == 0 {
+                                    post_md!("Maximum program steps must be at least 1.");
+                                    return;
+                                }
+
+                                if steps <= MAX_STEPS {
+                                    // The number of turns the game is expected to last at most.
+                                    let turns = game_config_mut!(|cfg| if cfg.is_configuring() {
+                                        cfg.steps = steps;
+                                        let board = &cfg.board;
+                                        Some(board.max_turns.map_or_else(
+                                            || board.buckets.iter().map(Bucket::capacity).sum(),
+                                            |turns| turns as usize,
+                                        ))
+                                    } else {
+                                        None
+                                    });
+
+                                    match turns {
+                                        // A loop-free move on turn n may be n commands long.
+                                        Some(turns) if (steps as usize) < turns => post_md!(
+                                            "Maximum program steps updated to {}.\nWarning: full-length moves will run out of steps from turn {} on.",
+                                            steps,
+                                            steps + 1
+                                        ),
+                                        Some(_) => post_md!("Maximum program steps updated to {}.", steps),
+                                        None => post_md!("{}", SETTINGS_LOCKED),
+                                    }
+
+                                    return;
+                                }
+                            }
+
+                            post_md!("Step count could not be parsed.");
+                        } else {
+                            post_md!("Specify the maximum amount of steps a Brainfuck code should run for before halting.");
+                        }
+                    }
+
+                    // Setups the board layout.
+                    Some("board") => {
+                        // Buckets may be separated by either spaces or commas.
+                        let spec = components
+                            .flat_map(|c| c.split(','))
+                            .filter(|c| !c.is_empty())
+                            .collect::<Vec<_>>()
+                            .join(",");
+                        if spec.is_empty() {
+                            post_md!("Configure the board. Specify the capacities of the buckets as a list separated by spaces or commas, such as \"10 8 6\". Several buckets with the same capacity can be written as \"10x5\", meaning five buckets of capacity 10.");
+                            return;
+                        }
+
+                        let capacities = match parse_spec(&spec) {
+                            Ok(capacities) => capacities,
+                            Err(err) => {
+                                post_md!("Could not parse board: {}.", err);
+                                return;
+                            }
+                        };
+
+                        // The limits apply to both tapes together.
+                        let (_, second) = game_config!(|cfg| cfg.board.tape_capacities());
+                        if board_too_large(&[capacities.as_slice(), &second].concat()) {
+                            post_md!(
+                                "Board is too large. There can be at most {} buckets, each with a capacity of at most {}, and a total capacity of at most {}.",
+                                MAX_BUCKETS,
+                                MAX_CAPACITY,
+                                MAX_TOTAL_CAPACITY
+                            );
+                        } else if game_config_mut!(|cfg| if cfg.is_configuring() {
+                            cfg.board.reset_with(capacities);
+                            true
+                        } else {
+                            false
+                        }) {
+                            post_md!("Board succesfully updated!");
+                        } else {
+                            post_md!("{}", SETTINGS_LOCKED);
+                        }
+                    }
+
+                    // Setups the layout of the second tape.
+                    Some("board2") => {
+                        // Buckets may be separated by either spaces or commas.
+                        let spec = components
+                            .flat_map(|c| c.split(','))
+                            .filter(|c| !c.is_empty())
+                            .collect::<Vec<_>>()
+                            .join(",");
+                        if spec.is_empty() {
+                            post_md!("Configure a second tape, played on alongside the first one. Specify the capacities of its buckets in the same format as for \"set board\", or \"off\" to play on a single tape. With extensions on, % switches between the tapes.");
+                            return;
+                        }
+
+                        let capacities = if spec == "off" {
+                            None
+                        } else {
+                            match parse_spec(&spec) {
+                                Ok(capacities) => Some(capacities),
+                                Err(err) => {
+                                    post_md!("Could not parse board: {}.", err);
+                                    return;
+                                }
+                            }
+                        };
+
+                        // The limits apply to both tapes together.
+                        let (first, _) = game_config!(|cfg| cfg.board.tape_capacities());
+                        let second = capacities.as_deref().unwrap_or_default();
+                        if board_too_large(&[first.as_slice(), second].concat()) {
+                            post_md!(
+                                "Board is too large. There can be at most {} buckets, each with a capacity of at most {}, and a total capacity of at most {}.",
+                                MAX_BUCKETS,
+                                MAX_CAPACITY,
+                                MAX_TOTAL_CAPACITY
+                            );
+                        } else if game_config_mut!(|cfg| if cfg.is_configuring() {
+                            cfg.board.set_second_tape(capacities);
+                            true
+                        } else {
+                            false
+                        }) {
+                            post_md!("Second tape succesfully updated!");
+                        } else {
+                            post_md!("{}", SETTINGS_LOCKED);
+                        }
+                    }
+
+                    // Setups whether the buckets are laid out as a grid.
+                    Some("grid") => {
+                        let layout = match components.next() {
+                            Some("off") => BoardLayout::Linear,
+                            Some(size) => match size.split_once('x').and_then(|(rows, cols)| {
+                                Some((rows.parse().ok()?, cols.parse().ok()?))
+                            }) {
+                                Some((rows, cols)) => BoardLayout::Grid { rows, cols },
+                                None => {
+                                    post_md!("Grid size could not be parsed.");
+                                    return;
+                                }
+                            },
+                            None => {
+                                post_md!("Specify the size of the grid the buckets are laid out in, as in 3x4 for 3 rows of 4 buckets, or \"off\" to lay them out in a line. The grid is filled row by row, and must have exactly as many cells as there are buckets. With extensions on, ( and ) move the pointer up and down.");
+                                return;
+                            }
+                        };
+
+                        match game_config_mut!(|cfg| if cfg.is_configuring() {
+                            Some(cfg.board.set_layout(layout))
+                        } else {
+                            None
+                        }) {
+                            Some(Ok(())) => post_md!("Layout updated to {}.", layout),
+                            Some(Err(err)) => post_md!("Layout could not be updated: {}.", err),
+                            None => post_md!("{}", SETTINGS_LOCKED),
+                        }
+                    }
+
+                    // Setups the names shown for the buckets.
+                    Some("labels") => {
+                        let labels: Vec<_> = components.map(str::to_owned).collect();
+
+                        if labels.is_empty() {
+                            post_md!("Name the buckets. Specify a label for each bucket as a list separated by spaces, or \"none\" to number them.");
+                        } else {
+                            let res = game_config_mut!(|cfg| {
+                                let labels = if labels == ["none"] {
+                                    (1..=cfg.board.buckets.len())
+                                        .map(|idx| idx.to_string())
+                                        .collect()
+                                } else {
+                                    labels
+                                };
+
+                                cfg.board.set_labels(labels)
+                            });
+
+                            match res {
+                                Ok(()) => post_md!("Labels succesfully updated!"),
+                                Err(err) => post_md!("Labels could not be updated: {}.", err),
+                            }
+                        }
+                    }
+
+                    // Setups the maximum number of steps any instruction runs for.
+                    Some("buffer") => {
+                        if let Some(component) = components.next() {
+                            if let Ok(buf) = component.parse::<u16>() {
+                                if game_config_mut!(|cfg| if cfg.is_configuring() {
+                                    cfg.board.buffer_buckets = buf;
+                                    true
+                                } else {
+                                    false
+                                }) {
+                                    post_md!("Number of buffer buckets updated to {}.", buf);
+                                } else {
+                                    post_md!("{}", SETTINGS_LOCKED);
+                                }
+                            } else {
+                                post_md!("Step count could not be parsed.");
+                            }
+                        } else {
+                            post_md!("Specify the maximum amount of steps a Brainfuck code should run for before halting.");
+                        }
+                    }
+
+                    // Setups whether the swap rule is in effect.
+                    Some("swap") => match components.next() {
+                        Some("on") => {
+                            game_config_mut!(|cfg| cfg.swap = true);
+                            post_md!("Swap rule enabled.");
+                        }
+
+                        Some("off") => {
+                            game_config_mut!(|cfg| cfg.swap = false);
+                            post_md!("Swap rule disabled.");
+                        }
+
+                        _ => {
+                            post_md!("Specify \"on\" or \"off\". When on, in two-player games, the second player may reply \"swap\" to the first move to take over that position.");
+                        }
+                    },
+
+                    // Setups whether moves keep count of the tokens they run.
+                    Some("profiling") => match components.next() {
+                        Some("on") => {
+                            game_config_mut!(|cfg| cfg.board.profiling = true);
+                            post_md!("Profiling enabled.");
+                        }
+
+                        Some("off") => {
+                            game_config_mut!(|cfg| {
+                                cfg.board.profiling = false;
+                                cfg.board.last_move_profile = None;
+                            });
+                            post_md!("Profiling disabled.");
+                        }
+
+                        _ => {
+                            post_md!("Specify \"on\" or \"off\". When on, the \"debug\" command shows how many of each command the last move ran.");
+                        }
+                    },
+
+                    // Setups whether locking a bucket empties its neighbors.
+                    Some("capture") => match components.next() {
+                        Some("on") => {
+                            game_config_mut!(|cfg| cfg.board.capture = true);
+                            post_md!("Capture rule enabled.");
+                        }
+
+                        Some("off") => {
+                            game_config_mut!(|cfg| cfg.board.capture = false);
+                            post_md!("Capture rule disabled.");
+                        }
+
+                        _ => {
+                            post_md!("Specify \"on\" or \"off\". When on, locking a bucket removes every counter from the unlocked buckets next to it.");
+                        }
+                    },
+
+                    // Setups whether players may unlock their opponents' buckets.
+                    Some("unlock") => match components.next() {
+                        Some("on") => {
+                            game_config_mut!(|cfg| cfg.board.unlock = true);
+                            post_md!("Unlocking enabled.");
+                        }
+
+                        Some("off") => {
+                            game_config_mut!(|cfg| cfg.board.unlock = false);
+                            post_md!("Unlocking disabled.");
+                        }
+
+                        _ => {
+                            post_md!("Specify \"on\" or \"off\". When on, a player may send \"unlock <bucket>\" instead of moving to remove the lock from an opponent's bucket, along with one of its counters.");
+                        }
+                    },
+
+                    // Setups the rule that decides who owns a full bucket.
+                    Some("lockrule") => {
+                        let rule = match components.next() {
+                            Some("uniform") => LockRule::Uniform,
+                            Some("majority") => LockRule::Majority,
+                            _ => {
+                                post_md!("Specify \"uniform\" for buckets to lock when full of a single player's counters, or \"majority\" for them to lock for whoever holds most of their counters when they become full.");
+                                return;
+                            }
+                        };
+
+                        game_config_mut!(|cfg| {
+                            cfg.board.lock_rule = rule;
+                            cfg.board.reset();
+                        });
+                        post_md!("Lock rule updated to {}.", rule);
+                    }
+
+                    // Setups the condition that ends the game.
+                    Some("winrule") => {
+                        let win_rule = match (components.next(), components.next().map(str::parse))
+                        {
+                            (Some("standard"), None) => WinRule::Standard,
+                            (Some("consecutive"), Some(Ok(k))) if k > 0 => WinRule::Consecutive(k),
+                            _ => {
+                                post_md!("Specify \"standard\" for the player with the most locked buckets to win once all but the buffer buckets are locked, or \"consecutive <k>\" for the first player to lock k adjacent buckets to win right away.");
+                                return;
+                            }
+                        };
+
+                        game_config_mut!(|cfg| cfg.board.win_rule = win_rule);
+                        post_md!("Win rule updated to {}.", win_rule);
+                    }
+
+                    // Setups how ties are broken.
+                    Some("tiebreak") => {
+                        let tie_break = match components.next() {
+                            Some("none") => TieBreak::None,
+                            Some("counters") => TieBreak::Counters,
+                            _ => {
+                                post_md!("Specify \"counters\" for ties to be broken by the number of counters each player has in unlocked buckets, or \"none\" to keep them.");
+                                return;
+                            }
+                        };
+
+                        game_config_mut!(|cfg| cfg.board.tie_break = tie_break);
+                        post_md!("Tie-break updated to {}.", tie_break);
+                    }
+
+                    // Setups whether unlocked buckets decay every round.
+                    Some("decay") => match components.next() {
+                        Some("on") => {
+                            game_config_mut!(|cfg| cfg.board.decay = true);
+                            post_md!("Decay enabled.");
+                        }
+
+                        Some("off") => {
+                            game_config_mut!(|cfg| cfg.board.decay = false);
+                            post_md!("Decay disabled.");
+                        }
+
+                        _ => {
+                            post_md!("Specify \"on\" or \"off\". When on, every unlocked bucket loses its topmost counter at the end of each round.");
+                        }
+                    },
+
+                    // Setups whether counters fall into the buckets to their left.
+                    Some("gravity") => match components.next() {
+                        Some("on") => {
+                            game_config_mut!(|cfg| cfg.board.gravity = true);
+                            post_md!("Gravity enabled.");
+                        }
+
+                        Some("off") => {
+                            game_config_mut!(|cfg| cfg.board.gravity = false);
+                            post_md!("Gravity disabled.");
+                        }
+
+                        _ => {
+                            post_md!("Specify \"on\" or \"off\". When on, at the end of each move, the topmost counter of every unlocked bucket falls into the bucket to its left while that one is unlocked and has room.");
+                        }
+                    },
+
+                    // Setups the channel where moves and commands are logged.
+                    Some("logchannel") => match components.next() {
+                        Some("off") => {
+                            game_config_mut!(|cfg| cfg.log_channel_id = None);
+                            post_md!("Logging disabled.");
+                        }
+
+                        Some(channel) => match channel
+                            .strip_prefix("<#")
+                            .and_then(|c| c.strip_suffix('>'))
+                            .and_then(|c| c.parse::<u64>().ok())
+                        {
+                            Some(id) => {
+                                game_config_mut!(|cfg| cfg.log_channel_id = Some(ChannelId(id)));
+                                post!("Moves and commands will be logged to <#{}>.", id);
+                            }
+
+                            None => post_md!("Channel could not be parsed."),
+                        },
+
+                        None => {
+                            post_md!("Specify a channel such as #game-logs, or \"off\". Every move and command in this channel will be echoed there.");
+                        }
+                    },
+
+                    // Setups how deeply loops may be nested.
+                    Some("nesting") => match components.next().map(str::parse::<usize>) {
+                        Some(Ok(depth)) => {
+                            game_config_mut!(|cfg| cfg.dialect.max_nesting = depth);
+                            post_md!("Loops may now be nested up to {} deep.", depth);
+                        }
+
+                        Some(Err(_)) => post_md!("Nesting depth could not be parsed."),
+
+                        None => {
+                            post_md!("Specify how many loops may be nested inside each other, by default {}. Use 0 to forbid loops altogether.", Dialect::MAX_NESTING);
+                        }
+                    },
+
+                    // Setups how the counters on the board are drawn.
+                    Some("display") => match components.next() {
+                        Some("plain") => {
+                            game_config_mut!(|cfg| cfg.display_mode = DisplayMode::Plain);
+                            post_md!("Counters will be drawn as player symbols.");
+                        }
+
+                        Some("emoji") => {
+                            game_config_mut!(|cfg| cfg.display_mode = DisplayMode::Emoji);
+                            post_md!("Counters will be drawn as colored squares.");
+                        }
+
+                        _ => {
+                            post_md!("Specify \"plain\" or \"emoji\". In emoji mode, each player's counters are drawn as squares of their own color, and free spaces as white squares.");
+                        }
+                    },
+
+                    // Setups whether tied games go into overtime.
+                    Some("overtime") => match components.next() {
+                        Some("on") => {
+                            game_config_mut!(|cfg| cfg.board.overtime = true);
+                            post_md!("Overtime enabled.");
+                        }
+
+                        Some("off") => {
+                            game_config_mut!(|cfg| cfg.board.overtime = false);
+                            post_md!("Overtime disabled.");
+                        }
+
+                        _ => {
+                            post_md!("Specify \"on\" or \"off\". When on, a game that would end in a tie continues while any bucket is unlocked, and the next player to lock one wins.");
+                        }
+                    },
+
+                    // Setups whether adjacent locks grant bonus moves.
+                    Some("adjacency") => match components.next() {
+                        Some("on") => {
+                            game_config_mut!(|cfg| cfg.adjacency = true);
+                            post_md!("Adjacency bonus enabled.");
+                        }
+
+                        Some("off") => {
+                            game_config_mut!(|cfg| cfg.adjacency = false);
+                            post_md!("Adjacency bonus disabled.");
+                        }
+
+                        _ => {
+                            post_md!("Specify \"on\" or \"off\". When on, locking a bucket next to another one of yours lets you send a single extra command right away.");
+                        }
+                    },
+
+                    // Setups whether game records are posted when games end.
+                    Some("notation") => match components.next() {
+                        Some("pgn") => {
+                            game_config_mut!(|cfg| cfg.pgn = true);
+                            post_md!("Game records will be posted when games end.");
+                        }
+
+                        Some("off") => {
+                            game_config_mut!(|cfg| cfg.pgn = false);
+                            post_md!("Game records will no longer be posted.");
+                        }
+
+                        _ => {
+                            post_md!("Specify \"pgn\" or \"off\". When set to pgn, the record of each game is posted when it ends. Records can be shown with \"export\" and replayed with \"import\".");
+                        }
+                    },
+
+                    // Setups whether players bid for the first move.
+                    Some("bidding") => match components.next() {
+                        Some("on") => {
+                            game_config_mut!(|cfg| cfg.bidding = true);
+                            post_md!("Bidding enabled.");
+                        }
+
+                        Some("off") => {
+                            game_config_mut!(|cfg| cfg.bidding = false);
+                            post_md!("Bidding disabled.");
+                        }
+
+                        _ => {
+                            post_md!("Specify \"on\" or \"off\". When on, players send \"bid <n>\" before the game starts, and the highest bidder moves first, but their first n moves are one character shorter.");
+                        }
+                    },
+
+                    // Setups the bonus for the last player in the turn order.
+                    Some("komi") => {
+                        if let Some(component) = components.next() {
+                            match component.parse::<f64>() {
+                                Ok(komi) if komi.is_finite() && komi >= 0.0 => {
+                                    game_config_mut!(|cfg| cfg.board.komi = komi);
+                                    post_md!("Komi updated to {}.", komi);
+                                }
+
+                                _ => post_md!("Komi could not be parsed."),
+                            }
+                        } else {
+                            post_md!("Specify the bonus added to the score of the last player in the turn order, such as 0.5 to prevent ties.");
+                        }
+                    }
+
+                    // Setups how many counters each player may remove.
+                    Some("decrements") => {
+                        let kind = components.next();
+                        let max = components.next().map(str::parse::<usize>);
+
+                        let limit = match (kind, max) {
+                            (Some("off"), None) => None,
+                            (Some("per-move"), Some(Ok(max))) => Some(DecrementLimit::PerMove(max)),
+                            (Some("per-game"), Some(Ok(max))) => Some(DecrementLimit::PerGame(max)),
+                            (None, _) => {
+                                post_md!("Specify \"per-move <n>\" or \"per-game <n>\" to limit how many counters each player may remove, or \"off\" for no limit.");
+                                return;
+                            }
+                            _ => {
+                                post_md!("Decrement limit could not be parsed.");
+                                return;
+                            }
+                        };
+
+                        game_config_mut!(|cfg| {
+                            cfg.board.decrement_limit = limit;
+                            cfg.board.reset();
+                        });
+
+                        match limit {
+                            Some(limit) => post_md!("Decrement limit updated to {}.", limit),
+                            None => post_md!("Decrement limit disabled."),
+                        }
+                    }
+
+                    // Setups whether the extended commands are available.
+                    Some("extensions") => match components.next() {
+                        Some("on") => {
+                            game_config_mut!(|cfg| cfg.dialect.extensions = true);
+                            post_md!("Extensions enabled.");
+                        }
+
+                        Some("off") => {
+                            game_config_mut!(|cfg| cfg.dialect.extensions = false);
+                            post_md!("Extensions disabled.");
+                        }
+
+                        _ => {
+                            post_md!("Specify \"on\" or \"off\". When on, @<n> moves the pointer straight to bucket n, counting from 1, ^ moves it to the leftmost unlocked bucket, % switches to the other tape if there are two, ( and ) move it up and down if the buckets form a grid, ~ removes every counter from the current bucket, and { } loops while the top counter of the current bucket is yours.");
+                        }
+                    },
+
+                    // Setups whether moves may write the contents of buckets.
+                    Some("io") => match components.next() {
+                        Some("on") => {
+                            game_config_mut!(|cfg| cfg.dialect.io = true);
+                            post_md!("I/O enabled.");
+                        }
+
+                        Some("off") => {
+                            game_config_mut!(|cfg| cfg.dialect.io = false);
+                            post_md!("I/O disabled.");
+                        }
+
+                        _ => {
+                            post_md!("Specify \"on\" or \"off\". When on, . writes the counters in the current bucket to the output, which is posted after the move, and , adds as many counters as the next value given after a | at the end of the move, such as \"+,>, | 3 1\".");
+                        }
+                    },
+
+                    // Setups the characters moves are written with.
+                    Some("charset") => {
+                        let chars: Vec<_> = components.collect();
+
+                        let charset = match chars.as_slice() {
+                            [] => {
+                                post_md!("Specify \"standard\", or the six characters to use instead of + - < > [ ], separated by spaces. The standard characters keep working.");
+                                return;
+                            }
+                            ["standard"] => Some(BfCharset::Standard),
+                            _ if chars.len() == 6
+                                && chars.iter().all(|c| c.chars().count() == 1) =>
+                            {
+                                let mut custom = ['\0'; 6];
+                                for (c, chr) in custom.iter_mut().zip(&chars) {
+                                    *c = chr.chars().next().unwrap();
+                                }
+
+                                BfCharset::custom(custom)
+                            }
+                            _ => None,
+                        };
+
+                        if let Some(charset) = charset {
+                            post_md!("Charset updated to {}.", charset);
+                            game_config_mut!(|cfg| cfg.dialect.charset = charset);
+                        } else {
+                            post_md!(
+                                "Charset could not be updated: specify six distinct characters."
+                            );
+                        }
+                    }
+
+                    // Setups how many counters each player may place.
+                    Some("supply") => match components.next().map(|c| (c, c.parse::<usize>())) {
+                        Some(("off", _)) => {
+                            game_config_mut!(|cfg| {
+                                cfg.board.supply = None;
+                                cfg.board.reset();
+                            });
+                            post_md!("Supply limit disabled.");
+                        }
+
+                        Some((_, Ok(supply))) => {
+                            game_config_mut!(|cfg| {
+                                cfg.board.supply = Some(supply);
+                                cfg.board.reset();
+                            });
+                            post_md!("Supply updated to {} counters per player.", supply);
+                        }
+
+                        Some((_, Err(_))) => post_md!("Supply could not be parsed."),
+
+                        None => {
+                            post_md!("Specify the number of counters each player may place over the whole game, or \"off\" for no limit. Removing one of your own counters returns it to your supply.");
+                        }
+                    },
+
+                    // Setups the number of turns after which the game is adjudicated.
+                    Some("maxturns") => match components.next().map(|c| (c, c.parse::<u32>())) {
+                        Some(("off", _)) | Some((_, Ok(0))) => {
+                            game_config_mut!(|cfg| cfg.board.max_turns = None);
+                            post_md!("Turn limit disabled.");
+                        }
+
+                        Some((_, Ok(turns))) => {
+                            game_config_mut!(|cfg| cfg.board.max_turns = Some(turns));
+                            post_md!("Games will be adjudicated after {} turns.", turns);
+                        }
+
+                        Some((_, Err(_))) => post_md!("Turn limit could not be parsed."),
+
+                        None => {
+                            post_md!("Specify the number of turns after which an unfinished game is adjudicated, or \"off\" for no limit. Adjudicated games are won by whoever owns the most buckets, and then by whoever has the most counters on the board.");
+                        }
+                    },
+
+                    // Setups the turns at the start of the game during which loops are banned.
+                    Some("opening") => match (components.next(), components.next()) {
+                        (Some("off"), None) => {
+                            game_config_mut!(|cfg| cfg.board.opening_no_brackets = 0);
+                            post_md!("Opening restriction disabled.");
+                        }
+
+                        (Some("nobrackets"), Some(turns)) => match turns.parse::<u32>() {
+                            Ok(turns) => {
+                                game_config_mut!(|cfg| cfg.board.opening_no_brackets = turns);
+                                post_md!("Loops are banned for the first {} turns.", turns);
+                            }
+                            Err(_) => post_md!("Turn count could not be parsed."),
+                        },
+
+                        _ => {
+                            post_md!("Specify \"nobrackets <n>\" to ban loops, including ownership loops, during the first n turns, or \"off\" to allow them from the start.");
+                        }
+                    },
+
+                    // Setups how often the buckets shrink.
+                    Some("shrink") => match components.next().map(|c| (c, c.parse::<usize>())) {
+                        Some(("off", _)) | Some((_, Ok(0))) => {
+                            game_config_mut!(|cfg| cfg.board.shrink = None);
+                            post_md!("Shrinking disabled.");
+                        }
+
+                        Some((_, Ok(rounds))) => {
+                            game_config_mut!(|cfg| cfg.board.shrink = Some(rounds));
+                            post_md!(
+                                "Unlocked buckets will lose a space every {} rounds.",
+                                rounds
+                            );
+                        }
+
+                        Some((_, Err(_))) => post_md!("Round count could not be parsed."),
+
+                        None => {
+                            post_md!("Specify the number of full rounds after which every unlocked bucket loses a space, or \"off\" to keep their capacities. Buckets never shrink below their fill or below one, and those that become full lock right away.");
+                        }
+                    },
+
+                    // Setups the counters the buckets start with.
+                    Some("start") => {
+                        let components: Vec<_> = components.collect();
+                        let mut start = Vec::new();
+
+                        if components.is_empty() {
+                            post_md!("Configure the starting position. Specify the counters each bucket starts with as a list separated by spaces, such as \"2:XX 4:O#\", with # for neutral counters, or \"none\" for an empty board.");
+                            return;
+                        }
+
+                        if components != ["none"] {
+                            for component in components {
+                                let fill =
+                                    component.split_once(':').and_then(|(bucket, counters)| {
+                                        Some((
+                                            parse_bucket(Some(bucket))?,
+                                            counters
+                                                .chars()
+                                                .map(|c| Player::new(c).ok())
+                                                .collect::<Option<_>>()?,
+                                        ))
+                                    });
+
+                                if let Some(fill) = fill {
+                                    start.push(fill);
                                 } else {
-                                    players.push(Player::new(component.chars().next().unwrap()));
+                                    post_md!("Could not parse starting position.");
+                                    return;
                                 }
                             }
+                        }
+
+                        let res = game_config_mut!(|cfg| cfg.board.set_start(start));
+                        match res {
+                            Ok(()) => post_md!("Starting position succesfully updated!"),
+                            Err(err) => {
+                                post_md!("Starting position could not be updated: {}.", err)
+                            }
+                        }
+                    }
+
+                    _ => {
+                        post_md!("Sets various parameters of the game. These include:\n- players: the symbols used for each player.\n- board: the capacities of the buckets in the game.\n- board2: the capacities of the buckets on a second tape, if any.\n- grid: the rows and columns the buckets are laid out in, if any.\n- labels: the names shown for the buckets.\n- display: how the counters on the board are drawn.\n- logchannel: the channel where moves and commands are logged.\n- buffer: the amount of buckets that can remain unlocked when the game ends.\n- komi: the bonus for the last player in the turn order.\n- start: the counters the buckets start with.\n- supply: the number of counters each player may place.\n- decrements: the number of counters each player may remove.\n- swap: whether the second player may swap places after the first move.\n- bidding: whether players bid for the first move.\n- notation: whether game records are posted when games end.\n- lockrule: who owns a full bucket.\n- winrule: the condition that ends the game.\n- tiebreak: how ties on locked buckets are broken.\n- overtime: whether tied games continue until the next lock.\n- maxturns: the number of turns after which the game is adjudicated.\n- opening: the number of turns at the start during which loops are banned.\n- capture: whether locking a bucket empties its neighbors.\n- unlock: whether players may unlock their opponents' buckets.\n- decay: whether unlocked buckets lose a counter every round.\n- gravity: whether counters fall into the bucket to their left after every move.\n- shrink: how many rounds pass before unlocked buckets lose a space.\n- adjacency: whether locking a bucket next to another one of yours grants a bonus move.\n- steps: the maximum amount of computational steps allowed.\n- charset: the characters moves are written with.\n- extensions: whether the extended commands @<n>, ^, %, ( ), ~ and { } are available.\n- io: whether . writes the current bucket to the output, and , reads input given after a |.\n- nesting: how deeply loops may be nested.\n- profiling: whether moves keep count of the commands they run.")
+                    }
+                }
+            }
+
+            // Shows the current settings.
+            Some("settings") => {
+                post_md!("{}", game_config!(|cfg| cfg.settings()));
+            }
+
+            // Manages the channels the bot plays in.
+            Some("bfchannel") => {
+                if msg.guild_id.is_none() {
+                    post_md!("Channels can only be managed in a server.");
+                    return;
+                }
+
+                let add = match components.next() {
+                    Some("list") => {
+                        match msg_helper.allowlist().await {
+                            Some(channels) => {
+                                let channels: Vec<_> =
+                                    channels.iter().map(|id| format!("<#{}>", id)).collect();
+                                post!("The bot plays in {}.", channels.join(", "));
+                            }
+                            None => post_md!(
+                                "No channels have been added, so the bot plays in every channel."
+                            ),
+                        }
+
+                        return;
+                    }
+
+                    Some("add") => true,
+                    Some("remove") => false,
+
+                    _ => {
+                        post_md!("Specify \"add\" or \"remove\" followed by a channel, which defaults to this one, to choose the channels the bot plays in, or \"list\" to see them. While no channels have been added, the bot plays in every channel.");
+                        return;
+                    }
+                };
+
+                // Reads a channel mention, or a bare ID.
+                let channel_id = match components.next() {
+                    None => msg.channel_id,
+                    Some(channel) => match channel
+                        .trim_start_matches("<#")
+                        .trim_end_matches('>')
+                        .parse()
+                    {
+                        Ok(id) => ChannelId(id),
+                        Err(_) => {
+                            post_md!("Channel could not be parsed.");
+                            return;
+                        }
+                    },
+                };
+
+                if !msg_helper.is_admin(&msg).await {
+                    post_md!("Only admins can change the channels the bot plays in!");
+                    return;
+                }
+
+                match msg_helper.update_allowlist(channel_id, add).await {
+                    Ok(()) if add => post!("The bot now plays in <#{}>.", channel_id),
+                    Ok(()) => post!("The bot no longer plays in <#{}>.", channel_id),
+                    Err(why) => post_md!("Could not update the channels: {}.", why),
+                }
+            }
+
+            // Saves, shows or clears the settings new channels in the server start with.
+            Some("defaults") => {
+                if msg.guild_id.is_none() {
+                    post_md!("Defaults can only be used in a server.");
+                    return;
+                }
+
+                let defaults = match components.next() {
+                    Some("show") => {
+                        match msg_helper.guild_defaults().await {
+                            Some(defaults) => {
+                                post_md!("{}", GameConfig::from_defaults(defaults).settings())
+                            }
+                            None => post_md!("No defaults have been saved for this server."),
+                        }
+
+                        return;
+                    }
+
+                    Some("save") => Some(game_config!(|cfg| cfg.defaults())),
+                    Some("clear") => None,
+
+                    _ => {
+                        post_md!("Specify \"save\" to make the settings of this channel the default for new channels in the server, \"show\" to see the defaults, or \"clear\" to forget them. Settings changed in a channel still override the defaults.");
+                        return;
+                    }
+                };
+
+                if !msg_helper.is_admin(&msg).await {
+                    post_md!("Only admins can change the defaults!");
+                    return;
+                }
+
+                let saved = defaults.is_some();
+                match msg_helper.set_guild_defaults(defaults).await {
+                    Ok(()) if saved => {
+                        post_md!("Defaults saved! New channels will start with these settings.")
+                    }
+                    Ok(()) => post_md!("Defaults cleared."),
+                    Err(why) => post_md!("Could not update the defaults: {}.", why),
+                }
+            }
+
+            // Starts a new game.
+            Some("play") => match msg_helper.start_game(&msg, None, |_| {}).await {
+                Ok((_, reply)) | Err(reply) => msg_helper.post(reply).await,
+            },
+
+            // Challenges a user to a two-player game, with the current settings
+            // or the ones saved for the server.
+            Some("challenge") => {
+                let target = match msg.mentions.first() {
+                    Some(target) => target,
+                    None => {
+                        post_md!("Mention the user you want to challenge, optionally followed by \"defaults\" to play with the settings saved for the server. They then have {} minutes to reply \"accept\" or \"decline\".", CHALLENGE_TIMEOUT.as_secs() / 60);
+                        return;
+                    }
+                };
+
+                if target.id == msg.author.id || target.bot {
+                    post_md!("You can't challenge that user!");
+                    return;
+                }
+
+                // Skips the mention.
+                components.next();
+                let preset = match components.next() {
+                    None => None,
+                    Some("defaults") => match msg_helper.guild_defaults().await {
+                        Some(defaults) => Some(defaults),
+                        None => {
+                            post_md!("No settings have been saved for the server!");
+                            return;
+                        }
+                    },
+                    Some(_) => {
+                        post_md!(
+                            "The only preset is \"defaults\", the settings saved for the server."
+                        );
+                        return;
+                    }
+                };
+
+                let player_count = match &preset {
+                    Some(preset) => preset.board.player_count(),
+                    None => game_config!(|cfg| cfg.board.player_count()),
+                };
+
+                if player_count != 2 {
+                    post_md!("Challenges are only for two-player games!");
+                    return;
+                }
+
+                let challenge = Challenge {
+                    challenger: msg.author.id,
+                    target: target.id,
+                    preset,
+                    deadline: Instant::now() + CHALLENGE_TIMEOUT,
+                };
+
+                // An expired challenge is replaced.
+                if game_config_mut!(|cfg| {
+                    if cfg.challenge.as_ref().is_some_and(|c| !c.expired()) {
+                        false
+                    } else {
+                        cfg.challenge = Some(challenge);
+                        true
+                    }
+                }) {
+                    post!(
+                        "<@{}>, <@{}> challenges you to a game! Reply \"accept\" or \"decline\" within {} minutes.",
+                        target.id,
+                        msg.author.id,
+                        CHALLENGE_TIMEOUT.as_secs() / 60
+                    );
+                } else {
+                    post_md!("Another challenge is already waiting for an answer!");
+                }
+            }
+
+            // Accepts a challenge, and starts the game with both users seated.
+            Some("accept") => {
+                let id = msg.author.id;
+                let challenge = match game_config_mut!(|cfg| cfg.take_challenge(|c| c.target == id))
+                {
+                    Some(challenge) if challenge.expired() => {
+                        post_md!("The challenge expired!");
+                        return;
+                    }
+                    Some(challenge) => challenge,
+                    None => return,
+                };
+
+                let challenger = challenge.challenger;
+                let seat = |cfg: &mut GameConfig| {
+                    cfg.player_ids = vec![Some(challenger), Some(id)];
+                };
+
+                let (game_helper, reply) =
+                    match msg_helper.start_game(&msg, challenge.preset, seat).await {
+                        Ok(res) => res,
+                        Err(reply) => {
+                            msg_helper.post(reply).await;
+                            return;
+                        }
+                    };
+
+                msg_helper.post(reply).await;
+                if game_helper.game_config(GameConfig::is_active).await {
+                    game_helper
+                        .post(format!("<@{}>, you move first!", challenger))
+                        .await;
+                } else {
+                    game_helper
+                        .post(format!(
+                            "<@{}> <@{}>, bid for the first move!",
+                            challenger, id
+                        ))
+                        .await;
+                }
+            }
+
+            // Declines a challenge, or withdraws it.
+            Some("decline") => {
+                let id = msg.author.id;
+                if game_config_mut!(
+                    |cfg| cfg.take_challenge(|c| c.target == id || c.challenger == id)
+                )
+                .is_some()
+                {
+                    post_md!("The challenge was called off.");
+                }
+            }
+
+            // Shows the current state of the board.
+            Some("board") => {
+                let game_helper = match msg_helper
+                    .select_game(components.next(), GameConfig::is_active)
+                    .await
+                {
+                    Ok(game_helper) => game_helper,
+                    Err(reply) => {
+                        post_md!("{}", reply);
+                        return;
+                    }
+                };
+
+                post_md!(
+                    "{}",
+                    game_helper
+                        .game_config(|cfg| if cfg.is_active() {
+                            cfg.board.display(cfg.display_mode).to_string()
+                        } else {
+                            "No game is currently active!".to_owned()
+                        })
+                        .await
+                );
+            }
+
+            // Edits the board by hand, to set up custom positions.
+            Some("edit") => {
+                if !msg_helper.is_admin(&msg).await {
+                    post_md!("Only admins can edit the board!");
+                    return;
+                }
+
+                let res = game_config_mut!(|cfg| {
+                    if !cfg.is_configuring() {
+                        return "Cannot edit the board while a game is active!".to_owned();
+                    }
+
+                    match edit_board(&mut cfg.board, components) {
+                        Some(Ok(())) => format!(
+                            "Game not started — showing the edited position:\n{}",
+                            cfg.board.display(cfg.display_mode)
+                        ),
+                        Some(Err(err)) => format!("Could not edit the board: {}.", err),
+                        None => "Edits the board before a game starts. The subcommands are:\n- place <bucket> <symbol> <count>: adds counters to a bucket, with # for neutral counters.\n- remove <bucket> <count>: removes counters from a bucket.\n- pointer <bucket>: moves the pointer.\n- turn <n>: sets the turn number.\n- lock <bucket> [force]: recomputes whether a bucket is locked, or locks it regardless.".to_owned(),
+                    }
+                });
+
+                post_md!("{}", res);
+            }
+
+            // Shows how many games each user has won.
+            Some("stats") => {
+                let stats = game_config!(|cfg| cfg.stats());
+                msg_helper.post(stats).await;
+            }
+
+            // Shows how many buckets each player has locked.
+            Some("score") => {
+                post_md!(
+                    "{}",
+                    game_config!(|cfg| if cfg.is_active() {
+                        let board = &cfg.board;
+                        let mut res = String::new();
+
+                        for &player in &board.players {
+                            res += &format!("{}: {} locked\n", player, board.locked_by(player));
+                        }
+
+                        res + &format!("Contested buckets: {}", board.contested_count())
+                    } else {
+                        "No game is currently active!".to_owned()
+                    })
+                );
+            }
+
+            // Resets the game.
+            Some("reset") => {
+                let game_helper = match msg_helper
+                    .select_game(components.next(), |cfg| !cfg.is_configuring())
+                    .await
+                {
+                    Ok(game_helper) => game_helper,
+                    Err(reply) => {
+                        post_md!("{}", reply);
+                        return;
+                    }
+                };
+
+                let res = game_helper
+                    .game_config_mut(|cfg| {
+                        if !cfg.is_configuring() {
+                            cfg.reset();
+                            true
+                        } else {
+                            false
+                        }
+                    })
+                    .await;
+
+                if res {
+                    post_md!("Reset successful!");
+                    game_helper.finish_game().await;
+                } else {
+                    post_md!("No game is currently active!");
+                }
+            }
+
+            // Places a sealed bid for the first move.
+            Some("bid") => {
+                let amount = match components.next().map(str::parse) {
+                    Some(Ok(amount)) => amount,
+                    Some(Err(_)) => {
+                        post_md!("Bid could not be parsed.");
+                        return;
+                    }
+                    None => {
+                        post_md!("Bids for the first move. The highest bidder moves first, but their first n moves are one character shorter.");
+                        return;
+                    }
+                };
+
+                // Hides the bid from the other players. This needs the bot to be
+                // able to manage messages, so failures are ignored.
+                let _ = msg.delete(msg_helper.http()).await;
+
+                let game_helper = match msg_helper
+                    .select_game(components.next(), |cfg| {
+                        matches!(cfg.state, GameState::Bidding { .. })
+                    })
+                    .await
+                {
+                    Ok(game_helper) => game_helper,
+                    Err(reply) => {
+                        post_md!("{}", reply);
+                        return;
+                    }
+                };
+
+                match game_helper
+                    .game_config_mut(|cfg| cfg.bid(msg.author.id, amount))
+                    .await
+                {
+                    Some(BidOutcome::Waiting(n)) => {
+                        post_md!("{} placed a bid. Waiting for {} more.", msg.author.name, n)
+                    }
+                    Some(BidOutcome::Tie) => {
+                        post_md!("The highest bids were tied! Everyone must bid again.")
+                    }
+                    Some(BidOutcome::Done(id)) => {
+                        let board = game_helper
+                            .game_config(|cfg| cfg.board.display(cfg.display_mode).to_string())
+                            .await;
+                        post!(
+                            "<@{}> won the bidding and moves first.\n```{}```",
+                            id,
+                            board
+                        );
+                    }
+                    Some(BidOutcome::NotSeated) => {
+                        post_md!("Only the seated players may bid!")
+                    }
+                    None => post_md!("No bidding is currently taking place!"),
+                }
+            }
 
-                            match players.len() {
-                                0 => "Configure the players. Specify the characters that will be used to represent each player as a list separated by spaces.".to_owned(), 
-                                1 => "Players could not be updated: must be at least 2.".to_owned(),
-                                _ => {
-                                    let mut players_sorted = players.clone();
-                                    players_sorted.sort();
-
-                                    // Checks for repeat characters.
-                                    for i in 0..players_sorted.len() - 1 {
-                                        if players_sorted[i] == players_sorted[i + 1]{
-                                            return format!("Players could not be updated: repeated character {}.", players_sorted[i]);
-                                        }
-                                    }
+            // Posts the record of the current or last game.
+            Some("export") => {
+                post_md!(
+                    "{}",
+                    game_config!(|cfg| if !cfg.record.is_empty() {
+                        cfg.record.to_string()
+                    } else if !cfg.last_record.is_empty() {
+                        cfg.last_record.to_string()
+                    } else {
+                        "No game to export!".to_owned()
+                    })
+                );
+            }
 
-                                    cfg.board.players = Players::new(players);
-                                    "Players succesfully updated!".to_owned()
-                                }
+            // Replays a game record on a copy of the board.
+            Some("import") => {
+                let record = msg.content.trim_start()["import".len()..].trim();
+
+                if record.is_empty() {
+                    post_md!("Replays a game record such as 1. X \"+>\" 2. O \"-\" on the current board setup, and shows the final position. Other moves are written as words, such as 2. O swap, 5. X unlock 3, 5. X bonus \"+\" or 1. X handicap 2.");
+                    return;
+                }
+
+                let res = match Record::parse(record) {
+                    Ok(record) => game_config!(|cfg| {
+                        let mut board = cfg.board.clone();
+                        board.reset();
+
+                        match record.replay(&mut board, cfg.steps, &cfg.dialect) {
+                            Ok(skipped) if skipped.is_empty() => format!(
+                                "Replayed position, not a live game:\n{}",
+                                board.display(cfg.display_mode)
+                            ),
+                            Ok(skipped) => {
+                                let skipped: Vec<_> = skipped.iter().map(u32::to_string).collect();
+                                format!(
+                                    "Replayed position, not a live game:\n{}Turns with invalid characters were played as empty moves: {}.",
+                                    board.display(cfg.display_mode),
+                                    skipped.join(", ")
+                                )
                             }
-                        });
+                            Err(err) => format!("Could not replay the record: {}.", err),
+                        }
+                    }),
+                    Err(err) => format!("Could not parse the record: {}.", err),
+                };
 
-                        post_md!("{}", res);
+                post_md!("{}", res);
+            }
+
+            // Shows some information about a Brainfuck program, without running it.
+            Some("debug") => {
+                let code: String = components.collect();
+
+                if code.is_empty() {
+                    match game_config!(|cfg| cfg.board.last_move_profile) {
+                        Some(profile) => post_md!("Last move: {}.", profile),
+                        None => post_md!("Shows information about a Brainfuck program. With profiling on, shows what the last move ran instead."),
                     }
 
-                    // Setups the maximum number of steps any instruction runs for.
-                    Some("steps") => {
-                        if let Some(component) = components.next() {
-                            if let Ok(steps) = component.parse::<u32>() {
-                                if steps <= MAX_STEPS {
-                                    game_config_mut!(|cfg| cfg.steps = steps);
-                                    post_md!("Maximum program steps updated to {}.", steps);
-                                    return;
-                                }
-                            }
+                    return;
+                }
 
-                            post_md!("Step count could not be parsed.");
-                        } else {
-                            post_md!("Specify the maximum amount of steps a Brainfuck code should run for before halting.");
-                        }
+                let dialect = game_config!(|cfg| cfg.dialect.clone());
+                match Brainfuck::with_dialect(code, &dialect) {
+                    Ok(bf) => post_md!(
+                        "{}\n{} tokens: {} commands and {} loops.",
+                        bf.pretty_print(),
+                        bf.len(),
+                        bf.len_no_loops(),
+                        bf.loop_count()
+                    ),
+                    Err(err) => post_md!("Invalid program: {}.", err),
+                }
+            }
+
+            // Shows which single commands the player to move could play.
+            Some("legal") => {
+                let author = msg.author.id;
+                let res = game_config!(|cfg| {
+                    if !cfg.is_active() {
+                        return "No game is currently active!".to_owned();
                     }
 
-                    // Setups the board layout.
-                    Some("board") => {
-                        let mut capacities = Vec::new();
+                    let is_mover = match cfg.id() {
+                        Some(id) => id == author,
+                        None => !cfg.player_ids.contains(&Some(author)),
+                    };
 
-                        for component in components {
-                            if let Ok(num) = component.parse::<u16>() {
-                                capacities.push(num as usize);
-                            } else {
-                                post_md!("Could not parse board.");
-                                break;
-                            }
+                    if !is_mover {
+                        return format!(
+                            "Only the player to move, {}, can ask for their legal moves!",
+                            cfg.active_player_display()
+                        );
+                    }
+
+                    let board = &cfg.board;
+                    let mut res = String::new();
+
+                    for (cmd, legal) in board.legal_commands() {
+                        match legal {
+                            Ok(()) => writeln!(res, "{}: legal", cmd).unwrap(),
+                            Err(err) => writeln!(res, "{}: illegal, {}", cmd, err).unwrap(),
                         }
+                    }
+
+                    if board.bucket_count() <= MAX_NEAR_LOCK_BUCKETS {
+                        let near: Vec<_> = board
+                            .near_locks()
+                            .into_iter()
+                            .map(|idx| match &board.buckets[idx].label {
+                                Some(label) => label.clone(),
+                                None => (idx + 1).to_string(),
+                            })
+                            .collect();
 
-                        if capacities.is_empty() {
-                            post_md!("Configure the board. Specify the capacities of the buckets as a list separated by spaces.");
+                        if near.is_empty() {
+                            res += "No bucket is one counter away from locking.";
                         } else {
-                            game_config_mut!(|cfg| cfg.board.reset_with(capacities));
-                            post_md!("Board succesfully updated!");
+                            write!(res, "One counter away from locking: {}", near.join(", "))
+                                .unwrap();
                         }
                     }
 
-                    // Setups the maximum number of steps any instruction runs for.
-                    Some("buffer") => {
-                        if let Some(component) = components.next() {
-                            if let Ok(buf) = component.parse::<u16>() {
-                                game_config_mut!(|cfg| cfg.board.buffer_buckets = buf);
-                                post_md!("Number of buffer buckets updated to {}.", buf);
-                            } else {
-                                post_md!("Step count could not be parsed.");
-                            }
+                    res
+                });
+
+                post_md!("{}", res.trim_end());
+            }
+
+            // Defines, lists or removes the macros of the channel.
+            Some("define") => match components.next() {
+                None => {
+                    post_md!("Defines a macro, which is expanded wherever $name appears in a move and counts towards its length. Specify a name and its code, such as \"define fill +[+]\". Use \"define list\" to show the macros, and \"define remove <name>\" to delete one.");
+                }
+
+                Some("list") => {
+                    let res = game_config!(|cfg| {
+                        let macros: Vec<_> = cfg
+                            .macros
+                            .iter()
+                            .map(|(name, code)| format!("${}: {}", name, code))
+                            .collect();
+
+                        if macros.is_empty() {
+                            "No macros have been defined.".to_owned()
                         } else {
-                            post_md!("Specify the maximum amount of steps a Brainfuck code should run for before halting.");
+                            macros.join("\n")
                         }
+                    });
+                    post_md!("{}", res);
+                }
+
+                Some("remove") => {
+                    let name = components
+                        .next()
+                        .unwrap_or_default()
+                        .trim_start_matches('$');
+                    if game_config_mut!(|cfg| cfg.macros.remove(name)).is_some() {
+                        post_md!("Macro ${} removed.", name);
+                    } else {
+                        post_md!("There is no macro named ${}.", name);
+                    }
+                }
+
+                Some(name) => {
+                    let name = name.trim_start_matches('$');
+                    let code = components.collect::<Vec<_>>().join(" ");
+
+                    if name.is_empty()
+                        || name.chars().count() > MAX_MACRO_NAME
+                        || !name.chars().all(is_macro_char)
+                    {
+                        post_md!(
+                            "Macro names must be at most {} letters, digits or underscores.",
+                            MAX_MACRO_NAME
+                        );
+                        return;
                     }
 
-                    _ => {
-                        post_md!("Sets various parameters of the game. These include:\n- players: the symbols used for each player.\n- board: the capacities of the buckets in the game.\n- buffer: the amount of buckets that can remain unlocked when the game ends.\n- steps: the maximum amount of computational steps allowed.")
+                    if code.is_empty() || code.chars().count() > MAX_MACRO_LEN {
+                        post_md!(
+                            "Macro code must be between 1 and {} characters.",
+                            MAX_MACRO_LEN
+                        );
+                        return;
+                    }
+
+                    // Moves are expanded in a single pass, so a macro can't
+                    // refer to another one.
+                    let res = game_config_mut!(|cfg| {
+                        Brainfuck::with_dialect(&code, &cfg.dialect)
+                            .map_err(|err| format!("Invalid macro: {}.", err))?;
+
+                        if cfg.macros.len() >= MAX_MACROS && !cfg.macros.contains_key(name) {
+                            return Err(format!("There can be at most {} macros.", MAX_MACROS));
+                        }
+
+                        cfg.macros.insert(name.to_owned(), code.clone());
+                        Ok(())
+                    });
+
+                    match res {
+                        Ok(()) => post_md!("Macro ${} defined as {}.", name, code),
+                        Err(err) => post_md!("{}", err),
                     }
                 }
-            }
+            },
 
-            // Starts a new game.
-            Some("play") => {
-                let board = game_config_mut!(|cfg| {
-                    if cfg.active {
-                        return None;
+            // Runs an ordinary Brainfuck program, separately from the game.
+            Some("bf") => {
+                let rest = msg.content.trim_start()["bf".len()..].trim();
+
+                if rest.is_empty() {
+                    post_md!("Runs an ordinary Brainfuck program on a tape of {} byte cells, for at most {} steps. Text after a | is given to the program as input.", Tape::LEN, MAX_BF_STEPS);
+                    return;
+                }
+
+                let (code, input) = match rest.find('|') {
+                    Some(split) => (&rest[..split], rest[split + 1..].trim()),
+                    None => (rest, ""),
+                };
+
+                let dialect = Dialect {
+                    io: true,
+                    max_nesting: usize::MAX,
+                    ..Dialect::default()
+                };
+
+                let mut bf = match Brainfuck::with_dialect(code, &dialect) {
+                    Ok(bf) => bf,
+                    Err(err) => {
+                        post_md!("Invalid program: {}.", err);
+                        return;
                     }
+                };
 
-                    cfg.active = true;
-                    Some(cfg.board.to_string())
-                });
+                bf.set_input(input.bytes().map(usize::from));
+                let mut tape = Tape::new();
+                let res = bf.run(&mut tape, MAX_BF_STEPS, None);
 
-                if let Some(board) = board {
-                    post_md!("{}", board);
-                } else {
-                    post_md!("A game is already active!");
+                // Backticks would end the code block early.
+                let mut output: String = tape
+                    .output()
+                    .chars()
+                    .take(MAX_BF_OUTPUT)
+                    .map(|c| if c == '`' { '\'' } else { c })
+                    .collect();
+                if output.is_empty() {
+                    output = "(no output)".to_owned();
                 }
-            }
 
-            // Shows the current state of the board.
-            Some("board") => {
-                post_md!(
-                    "{}",
-                    game_config!(|cfg| if cfg.active {
-                        cfg.board.to_string()
-                    } else {
-                        "No game is currently active!".to_owned()
-                    })
-                );
+                match res {
+                    Ok(steps) => post_md!("{}\nFinished in {} steps.", output, steps),
+                    Err(err) => post_md!("{}\nStopped: {}.", output, err),
+                }
             }
 
-            // Resets the game.
-            Some("reset") => {
-                let res = game_config_mut!(|cfg| if cfg.active {
-                    cfg.reset();
-                    true
-                } else {
-                    false
-                });
+            // Checks whether a Brainfuck program parses, without running it.
+            Some("check") => {
+                let code = msg.content.trim_start()["check".len()..].trim();
 
-                if res {
-                    post_md!("Reset successful!");
-                } else {
-                    post_md!("No game is currently active!");
+                if code.is_empty() {
+                    post_md!("Checks whether a move is syntactically valid, without playing it.");
+                    return;
+                }
+
+                let (dialect, steps) = game_config!(|cfg| (cfg.dialect.clone(), cfg.steps));
+                match Brainfuck::with_dialect(code, &dialect) {
+                    Ok(bf) => post_md!(
+                        "Syntactically valid, {} tokens (fits turn ≥ {}). Loops may still run out of the {} step limit.",
+                        bf.len(),
+                        bf.len().max(1),
+                        steps
+                    ),
+                    Err(err) => post_md!("Invalid program: {}.", err),
                 }
             }
 
@@ -385,94 +3655,262 @@ impl EventHandler for GameHandler {
                 }
             }
 
-            // Any message that isn't a command. It might be a move in the game,
-            // or perhaps a skip.
-            component => {
-                let id = msg.author.id;
-                let mut player = Default::default();
+            // Starts a puzzle, or shows the current one.
+            Some("puzzle") => {
+                let puzzles = msg_helper.puzzles().await;
+
+                let puzzle = match components.next().map(str::parse::<usize>) {
+                    Some(Ok(n)) if (1..=puzzles.len()).contains(&n) => {
+                        let puzzle = puzzles[n - 1].clone();
+                        game_config_mut!(|cfg| cfg.puzzle.insert(puzzle).to_string())
+                    }
+
+                    Some(_) => {
+                        post_md!("Puzzle number must be between 1 and {}.", puzzles.len());
+                        return;
+                    }
+
+                    None => game_config_mut!(|cfg| cfg
+                        .puzzle
+                        .get_or_insert_with(|| {
+                            let idx = Rng::from_time().below(puzzles.len());
+                            puzzles[idx].clone()
+                        })
+                        .to_string()),
+                };
+
+                post_md!("{}\nSubmit your answer with \"solve <code>\".", puzzle);
+            }
+
+            // Attempts to solve the current puzzle.
+            Some("solve") => {
+                let code: String = components.collect();
 
                 let res = game_config_mut!(|cfg| {
-                    player = cfg.board.player();
+                    let res = cfg.puzzle.as_ref()?.attempt(&code, cfg.steps);
 
-                    // In case of a skip, runs the empty string as code.
-                    let content = if component == Some("skip") {
-                        ""
-                    } else {
-                        &msg.content
-                    };
+                    if res == Ok(true) {
+                        cfg.puzzle = None;
+                    }
 
-                    // Checks the message author's ID.
-                    match cfg.id() {
-                        Some(new_id) => {
-                            // Ignore messages from the incorrect player.
-                            if new_id != id {
-                                return None;
-                            }
+                    Some(res)
+                });
+
+                match res {
+                    Some(Ok(true)) => post!("<@{}> solved the puzzle!", msg.author.id),
+                    Some(Ok(false)) => post_md!("Not quite!"),
+                    Some(Err(err)) => post_md!("Not quite: {}.", err),
+                    None => post_md!("No puzzle is currently active!"),
+                }
+            }
+
+            // Plays games between computer players on the current settings.
+            Some("simulate") => {
+                if !msg_helper.is_admin(&msg).await {
+                    post_md!("Only admins can run simulations!");
+                    return;
+                }
+
+                match components.next().map(str::parse::<usize>) {
+                    Some(Ok(n)) if (1..=MAX_SIMULATIONS).contains(&n) => {
+                        // The agents take the seats in order, cycling if
+                        // there are fewer of them.
+                        let mut names: Vec<_> = components.collect();
+                        if names.is_empty() {
+                            names.push(AGENTS[0]);
                         }
 
-                        None => {
-                            // Ignore messages from repeat users.
-                            for &old_id in &cfg.player_ids {
-                                if old_id == id {
-                                    return None;
+                        let mut rng = Rng::from_time();
+                        let mut agents = Vec::new();
+                        for name in names {
+                            match new_agent(name, Rng::new(rng.next_u64())) {
+                                Some(agent) => agents.push(agent),
+                                None => {
+                                    post_md!(
+                                        "There's no agent named {}. The agents are {}.",
+                                        name,
+                                        AGENTS.join(", ")
+                                    );
+                                    return;
                                 }
                             }
                         }
-                    }
 
-                    // Evaluates the message as Brainfuck code.
-                    if let Some(res) = cfg.eval(content) {
-                        // Posts any error, except those by invalid moves, as
-                        // they're probably just comments.
-                        if let Err(err) = res {
-                            if matches!(err, EvalError::InvalidChar { .. }) {
-                                None
-                            } else {
-                                Some(format_md!("Invalid move: {}.", err))
-                            }
-                        }
-                        // A move was succesfully made.
-                        else {
-                            // Adds the player to the player list.
-                            if cfg.player_ids.len() < cfg.board.player_count() {
-                                cfg.player_ids.push(id);
+                        let (board, rules) = game_config!(|cfg| (
+                            cfg.board.clone(),
+                            SimRules {
+                                dialect: cfg.dialect.clone(),
+                                steps: cfg.steps,
                             }
+                        ));
 
-                            Some(
-                                // Posts the winners.
-                                if let Some(winners) = cfg.board.winners() {
-                                    let res = format_md!("{}\n{}", winners, cfg.board);
-                                    cfg.reset();
-                                    res
-                                }
-                                // Posts the current state of the board, together with the poster.
-                                else if let Some(id) = cfg.id() {
-                                    format!("<@{}>\n```{}```", id, cfg.board)
-                                }
-                                // Posts the current state of the board.
-                                else {
-                                    format_md!("{}", cfg.board)
-                                },
-                            )
+                        post_md!("Simulating {} games...", n);
+
+                        // Simulations are CPU-bound, so they're run off the gateway thread.
+                        let handle =
+                            tokio::task::spawn_blocking(move || simulate(board, agents, n, &rules));
+
+                        match handle.await {
+                            Ok(stats) => post_md!("{}", stats),
+                            Err(why) => {
+                                println!("Error running simulation: {:?}", why);
+                                post_md!("The simulation failed.");
+                            }
                         }
                     }
-                    // The game is inactive.
-                    else {
-                        None
+
+                    Some(_) => {
+                        post_md!("Number of games must be between 1 and {}.", MAX_SIMULATIONS)
+                    }
+
+                    None => {
+                        post_md!("Simulates games between computer players on the current settings. Specify the number of games, at most {}, followed by the agents playing each seat, such as \"simulate 100 greedy random\". The agents are {}, and default to {}.", MAX_SIMULATIONS, AGENTS.join(", "), AGENTS[0])
+                    }
+                }
+            }
+
+            // Shows how long moves have taken to run.
+            Some("perf") => {
+                if !msg_helper.is_admin(&msg).await {
+                    post_md!("Only admins can view performance statistics!");
+                    return;
+                }
+
+                let counters = msg_helper.perf_counters().await;
+                let mut res = game_config!(|cfg| {
+                    let mut res = String::from("This channel:\n");
+                    for evaluation in cfg.perf.slowest(SLOWEST_EVALUATIONS) {
+                        writeln!(res, "{}", evaluation).unwrap();
+                    }
+
+                    if let Some(average) = cfg.perf.average() {
+                        writeln!(res, "Recent average: {}", fmt_duration(average)).unwrap();
                     }
+
+                    writeln!(res, "Out of steps: {}", cfg.perf.max_steps_failures()).unwrap();
+                    res
                 });
 
+                writeln!(res, "\nAll channels:").unwrap();
+                writeln!(res, "Moves run: {}", counters.evaluations()).unwrap();
+                if let Some(average) = counters.average() {
+                    writeln!(res, "Average: {}", fmt_duration(average)).unwrap();
+                }
+
+                write!(res, "Out of steps: {}", counters.max_steps_failures()).unwrap();
+                post_md!("{}", res);
+            }
+
+            // Any message that isn't a command. It might be a move in the game,
+            // or perhaps a skip.
+            _ => {
+                let id = msg.author.id;
+                let (msg_helper, content, offset) =
+                    match msg_helper.route_move(id, &msg.content).await {
+                        Ok(route) => route,
+                        Err(reply) => {
+                            if let Some(reply) = reply {
+                                msg_helper.post(reply).await;
+                            }
+
+                            return;
+                        }
+                    };
+
+                let mut player = Default::default();
+                let mut outcome = None;
+                let (res, moved, turn, evaluation) = msg_helper
+                    .game_config_mut(|cfg| {
+                        let turn = cfg.board.turn;
+                        let res = cfg.play_move(
+                            id,
+                            &msg.author.name,
+                            content,
+                            offset,
+                            &mut player,
+                            &mut outcome,
+                        );
+                        let evaluation = cfg.perf.take_unreported();
+                        (res, cfg.board.turn != turn, turn, evaluation)
+                    })
+                    .await;
+
+                msg_helper.report_evaluation(evaluation).await;
+                if let Some(outcome) = outcome {
+                    msg_helper
+                        .log_move(&msg.author.name, player, content, turn, outcome)
+                        .await;
+                }
+
                 // Posts message, updates nickname.
                 if let Some(post) = res {
-                    post!("{}", post);
+                    msg_helper
+                        .post_move(post, moved, id, player, msg.guild_id)
+                        .await;
+                }
+            }
+        }
+    }
 
-                    msg.guild_id
-                        .unwrap()
-                        .edit_member(&ctx.http, id, |m| m.nickname(player.to_string()))
-                        .await
-                        .unwrap();
+    // Reactions to the board stand for the corresponding commands, and are
+    // processed as if the user had typed them.
+    async fn reaction_add(&self, ctx: Context, reaction: Reaction) {
+        let code = match &reaction.emoji {
+            ReactionType::Unicode(emoji) => {
+                match REACTION_MOVES.iter().find(|(e, _)| *e == emoji.as_str()) {
+                    Some(&(_, code)) => code,
+                    None => return,
                 }
             }
+            _ => return,
+        };
+
+        // Ignores the bot's own reactions.
+        let user = match reaction.user(&ctx).await {
+            Ok(user) if !user.bot => user,
+            _ => return,
+        };
+
+        let mut msg_helper =
+            MessageHelper::from_channel(&ctx, reaction.channel_id, reaction.guild_id);
+        if !msg_helper.is_allowed().await || !msg_helper.is_gamer(&user).await {
+            return;
+        }
+
+        // Finds the game whose board was reacted to.
+        for (game, lock) in msg_helper.games().await {
+            if lock.read().await.board_message == Some(reaction.message_id) {
+                msg_helper = msg_helper.with_game(game);
+                break;
+            }
+        }
+
+        let mut player = Default::default();
+        let mut outcome = None;
+        let (res, moved, turn, evaluation) = msg_helper
+            .game_config_mut(|cfg| {
+                let turn = cfg.board.turn;
+                if cfg.board_message != Some(reaction.message_id) {
+                    return (None, false, turn, None);
+                }
+
+                let res = cfg.play_move(user.id, &user.name, code, 0, &mut player, &mut outcome);
+                let evaluation = cfg.perf.take_unreported();
+                (res, cfg.board.turn != turn, turn, evaluation)
+            })
+            .await;
+
+        msg_helper.report_evaluation(evaluation).await;
+        if let Some(outcome) = outcome {
+            msg_helper
+                .log_move(&user.name, player, code, turn, outcome)
+                .await;
+        }
+
+        if let Some(post) = res {
+            msg_helper
+                .post_move(post, moved, user.id, player, reaction.guild_id)
+                .await;
         }
     }
 
@@ -486,3 +3924,238 @@ impl EventHandler for GameHandler {
         println!("{} is connected!", ready.user.name);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sends a message as the given user, returning the result of the move
+    /// if it was run.
+    fn play(cfg: &mut GameConfig, user: u64, content: &str) -> Option<EvalResult<()>> {
+        let mut player = cfg.board.player();
+        let mut outcome = None;
+        cfg.play_move(UserId(user), "user", content, 0, &mut player, &mut outcome);
+        outcome
+    }
+
+    #[test]
+    fn seats_follow_player_count() {
+        let mut cfg = GameConfig::default();
+        cfg.set_player_count(3).unwrap();
+        cfg.start();
+        assert_eq!(cfg.player_ids.len(), 3);
+
+        for user in 1..=3 {
+            assert_eq!(play(&mut cfg, user, "+"), Some(Ok(())));
+        }
+
+        // The seats are taken, and cycle back to the first player.
+        assert_eq!(play(&mut cfg, 4, "+"), None);
+        assert_eq!(cfg.id(), Some(UserId(1)));
+
+        cfg.reset();
+        cfg.set_player_count(2).unwrap();
+        cfg.start();
+        assert_eq!(cfg.player_ids.len(), 2);
+        assert_eq!(cfg.board.player_count(), 2);
+    }
+
+    #[test]
+    fn stale_start_is_dropped() {
+        let y = Player::new('Y').unwrap();
+        let mut cfg = GameConfig::default();
+        cfg.set_player_count(3).unwrap();
+        cfg.board.set_start(vec![(0, vec![y, y])]).unwrap();
+
+        cfg.set_player_count(2).unwrap();
+        assert!(cfg.board.start.is_empty());
+
+        cfg.start();
+        assert_eq!(play(&mut cfg, 1, "+"), Some(Ok(())));
+        assert_eq!(cfg.board.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn ids_follow_seats_for_every_player_count() {
+        for count in 2..=DEFAULT_PLAYER_SYMBOLS.len() {
+            let mut cfg = GameConfig::default();
+            cfg.set_player_count(count as u8).unwrap();
+            cfg.start();
+
+            for user in 0..count as u64 {
+                assert_eq!(cfg.id(), None);
+                assert_eq!(play(&mut cfg, user, "skip"), Some(Ok(())));
+            }
+
+            for turn in 0..2 * count as u64 {
+                assert_eq!(cfg.id(), Some(UserId(turn % count as u64)));
+                assert_eq!(play(&mut cfg, turn % count as u64, ""), Some(Ok(())));
+            }
+        }
+    }
+
+    #[test]
+    fn bids_seat_three_players() {
+        let mut cfg = GameConfig::default();
+        cfg.set_player_count(3).unwrap();
+        cfg.bidding = true;
+        cfg.start();
+
+        assert!(matches!(
+            cfg.bid(UserId(1), 0),
+            Some(BidOutcome::Waiting(2))
+        ));
+        assert!(matches!(
+            cfg.bid(UserId(2), 2),
+            Some(BidOutcome::Waiting(1))
+        ));
+        assert!(matches!(
+            cfg.bid(UserId(3), 1),
+            Some(BidOutcome::Done(UserId(2)))
+        ));
+
+        // The highest bidder moves first, and the rest follow in bid order.
+        assert_eq!(
+            cfg.player_ids,
+            vec![Some(UserId(2)), Some(UserId(3)), Some(UserId(1))]
+        );
+        assert_eq!(cfg.id(), Some(UserId(2)));
+        assert_eq!(cfg.board.max_len(), 0);
+    }
+
+    #[test]
+    fn tied_players_all_win() {
+        let mut cfg = GameConfig {
+            board: GameBoard::new_symmetric(3, 1, 0),
+            ..GameConfig::default()
+        };
+        cfg.set_player_count(3).unwrap();
+        cfg.start();
+
+        for (user, program) in ["+", ">+", ">+"].iter().enumerate() {
+            assert_eq!(play(&mut cfg, user as u64, program), Some(Ok(())));
+        }
+
+        assert!(cfg.is_configuring());
+        for user in 0..3 {
+            assert_eq!(cfg.win_counts.get(&UserId(user)), Some(&1));
+        }
+    }
+
+    #[test]
+    fn macro_errors_point_at_the_move() {
+        let mut cfg = GameConfig::default();
+        cfg.macros.insert("up".to_owned(), "++".to_owned());
+        cfg.macros.insert("open".to_owned(), "[".to_owned());
+        cfg.start();
+
+        // An error after a macro counts the macro's name, not its code.
+        assert_eq!(
+            play(&mut cfg, 1, "$up]"),
+            Some(Err(EvalError::MismatchedRight { idx: 3 }))
+        );
+
+        // An error inside a macro points at its invocation, and names it.
+        let mut player = cfg.board.player();
+        let mut outcome = None;
+        let reply = cfg.play_move(UserId(1), "user", "+$open", 0, &mut player, &mut outcome);
+        assert_eq!(outcome, Some(Err(EvalError::MismatchedLeft { idx: 1 })));
+        assert_eq!(
+            reply,
+            Some(format_md!(
+                "Invalid move: {}, in macro $open.",
+                EvalError::MismatchedLeft { idx: 1 }
+            ))
+        );
+    }
+
+    #[test]
+    fn prefixed_errors_point_at_the_message() {
+        assert_eq!(parse_named_move("#2 +]"), Some((2, "+]", 3)));
+        assert_eq!(parse_named_move(" #12\u{3000}+"), Some((12, "+", 5)));
+        assert_eq!(parse_named_move("skip 3"), Some((3, "skip", 0)));
+        assert_eq!(parse_named_move("+]"), None);
+
+        let mut cfg = GameConfig::default();
+        cfg.macros.insert("open".to_owned(), "[".to_owned());
+        cfg.start();
+
+        let (_, content, offset) = parse_named_move("#2 +]").unwrap();
+        let mut player = cfg.board.player();
+        let mut outcome = None;
+        cfg.play_move(
+            UserId(1),
+            "user",
+            content,
+            offset,
+            &mut player,
+            &mut outcome,
+        );
+        assert_eq!(outcome, Some(Err(EvalError::MismatchedRight { idx: 4 })));
+
+        let (_, content, offset) = parse_named_move("#2 $open").unwrap();
+        cfg.play_move(
+            UserId(1),
+            "user",
+            content,
+            offset,
+            &mut player,
+            &mut outcome,
+        );
+        assert_eq!(outcome, Some(Err(EvalError::MismatchedLeft { idx: 3 })));
+    }
+
+    #[test]
+    fn thousands_are_separated() {
+        assert_eq!(separate_thousands(0), "0");
+        assert_eq!(separate_thousands(999), "999");
+        assert_eq!(separate_thousands(1000), "1,000");
+        assert_eq!(separate_thousands(123_456), "123,456");
+        assert_eq!(separate_thousands(u32::MAX), "4,294,967,295");
+    }
+
+    #[test]
+    fn swap_reminder_pings_known_players() {
+        let mut cfg = GameConfig {
+            swap: true,
+            ..GameConfig::default()
+        };
+        cfg.start();
+        cfg.player_ids[1] = Some(UserId(2));
+
+        let mut player = cfg.board.player();
+        let mut outcome = None;
+        let reply = cfg
+            .play_move(UserId(1), "user", "+", 0, &mut player, &mut outcome)
+            .unwrap();
+        assert!(reply.starts_with("<@2>"));
+        assert!(reply.contains("may reply \"swap\""));
+    }
+
+    #[test]
+    fn swaps_and_unlocks_are_recorded() {
+        let mut cfg = GameConfig {
+            board: GameBoard::new_symmetric(3, 2, 0),
+            swap: true,
+            ..GameConfig::default()
+        };
+        cfg.board.unlock = true;
+        cfg.start();
+        let start = cfg.board.clone();
+
+        // The first user keeps their seat, now playing as O.
+        for (user, content) in [(1, "+"), (2, "swap"), (1, ">++"), (2, "unlock 2")].iter() {
+            assert_eq!(play(&mut cfg, *user, content).unwrap_or(Ok(())), Ok(()));
+        }
+
+        let record = cfg.record.to_string();
+        assert_eq!(record, r#"1. X "+" 2. O swap 3. O ">++" 4. X unlock 2"#);
+
+        let mut board = start;
+        Record::parse(&record)
+            .unwrap()
+            .replay(&mut board, cfg.steps, &cfg.dialect)
+            .unwrap();
+        assert_eq!(board, cfg.board);
+    }
+}
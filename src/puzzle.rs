@@ -0,0 +1,132 @@
+//! Puzzles: preset positions where the side to move can reach some goal in a
+//! single move.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use crate::game::{EvalResult, GameBoard};
+
+use serde::{Deserialize, Serialize};
+
+/// The bundled puzzles, in JSON format.
+const PUZZLES: &str = include_str!("puzzles.json");
+
+/// The number of steps the bundled solutions are checked with.
+const SOLUTION_STEPS: u32 = 10_000;
+
+/// What the side to move must achieve in order to solve a puzzle.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Goal {
+    /// Win the game outright.
+    Win,
+
+    /// Lock the bucket with the given index.
+    Lock {
+        /// The index of the bucket.
+        bucket: usize,
+    },
+}
+
+/// A position together with a goal for the side to move.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Puzzle {
+    /// The name of the puzzle.
+    pub name: String,
+
+    /// The starting position.
+    pub board: GameBoard,
+
+    /// What must be achieved.
+    pub goal: Goal,
+
+    /// A known solution, used to validate the puzzle.
+    solution: String,
+}
+
+impl Display for Puzzle {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let player = self.board.player();
+
+        match self.goal {
+            Goal::Win => writeln!(f, "{}: {} to move and win.", self.name, player)?,
+            Goal::Lock { bucket } => writeln!(
+                f,
+                "{}: {} to move and lock bucket {}.",
+                self.name,
+                player,
+                bucket + 1
+            )?,
+        }
+
+        write!(f, "{}", self.board)
+    }
+}
+
+impl Puzzle {
+    /// Plays the given code on a copy of the puzzle position, and returns
+    /// whether it achieves the goal.
+    pub fn attempt(&self, str: &str, steps: u32) -> EvalResult<bool> {
+        let player = self.board.player();
+        let mut board = self.board.clone();
+        board.eval(str, steps)?;
+
+        Ok(match self.goal {
+            Goal::Win => board
+                .winners()
+                .is_some_and(|w| w.sole_winner() == Some(player)),
+            Goal::Lock { bucket } => board.buckets[bucket].owner == Some(player),
+        })
+    }
+
+    /// Checks that the puzzle is well-formed and that its solution works.
+    fn validate(&self) -> Result<(), String> {
+        let board = &self.board;
+
+        if board.bucket_count() == 0 {
+            return Err("the board has no buckets".to_owned());
+        }
+
+        if board.position >= board.bucket_count() {
+            return Err("the pointer is out of bounds".to_owned());
+        }
+
+        if board.player_count() < 2 {
+            return Err("there must be at least 2 players".to_owned());
+        }
+
+        if board.buffer_buckets as usize >= board.bucket_count() {
+            return Err("there are too many buffer buckets".to_owned());
+        }
+
+        if board.winners().is_some() {
+            return Err("the game is already over".to_owned());
+        }
+
+        if let Goal::Lock { bucket } = self.goal {
+            match board.buckets.get(bucket) {
+                Some(b) if b.locked => return Err("the goal bucket is already locked".to_owned()),
+                Some(_) => {}
+                None => return Err("the goal bucket is out of bounds".to_owned()),
+            }
+        }
+
+        match self.attempt(&self.solution, SOLUTION_STEPS) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err("the solution doesn't achieve the goal".to_owned()),
+            Err(err) => Err(format!("the solution is invalid: {}", err)),
+        }
+    }
+}
+
+/// Loads and validates the bundled puzzles.
+pub fn load() -> Result<Vec<Puzzle>, String> {
+    let puzzles: Vec<Puzzle> = serde_json::from_str(PUZZLES).map_err(|err| err.to_string())?;
+
+    for (idx, puzzle) in puzzles.iter().enumerate() {
+        puzzle
+            .validate()
+            .map_err(|err| format!("puzzle {} ({}): {}", idx + 1, puzzle.name, err))?;
+    }
+
+    Ok(puzzles)
+}
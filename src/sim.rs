@@ -0,0 +1,398 @@
+//! Bot-vs-bot simulations, used to test how balanced a given set of rules is.
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::game::{Dialect, EvalResult, GameBoard, Player};
+
+/// The number of turns after which a simulated game is abandoned.
+pub const SIM_MAX_TURNS: u32 = 500;
+
+/// A small xorshift pseudorandom number generator, good enough for picking moves.
+#[derive(Clone, Copy, Debug)]
+pub struct Rng(u64);
+
+impl Rng {
+    /// Initializes a new generator with the given seed.
+    pub fn new(seed: u64) -> Self {
+        // The state of a xorshift generator must never be zero.
+        Self(seed | 1)
+    }
+
+    /// Initializes a new generator seeded from the system clock.
+    pub fn from_time() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+
+        Self::new(nanos)
+    }
+
+    /// Returns the next pseudorandom number.
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Returns a pseudorandom number less than `n`, which must be nonzero.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+/// The settings of a game that moves are played with, other than the board.
+#[derive(Clone, Debug)]
+pub struct SimRules {
+    /// The variant of Brainfuck moves are written in.
+    pub dialect: Dialect,
+
+    /// The maximum number of steps a move may take.
+    pub steps: u32,
+}
+
+impl SimRules {
+    /// Plays a move written in standard Brainfuck on the given board.
+    fn play(&self, board: &mut GameBoard, code: &str) -> EvalResult<()> {
+        board.eval_with(
+            self.dialect.charset.translate(code),
+            self.steps,
+            &self.dialect,
+        )
+    }
+
+    /// Returns whether a move written in standard Brainfuck would be valid in
+    /// the given position.
+    fn is_valid(&self, board: &GameBoard, code: &str) -> bool {
+        self.play(&mut board.clone(), code).is_ok()
+    }
+}
+
+/// A computer player, which picks a move given the current state of the board.
+pub trait Agent: Send {
+    /// The name of the agent.
+    fn name(&self) -> &str;
+
+    /// Picks the code to play in the current position, written in standard
+    /// Brainfuck. Invalid code counts as a skip.
+    fn choose(&mut self, board: &GameBoard, rules: &SimRules) -> String;
+}
+
+/// An agent that plays random loop-free code.
+pub struct RandomAgent {
+    /// The random number generator.
+    rng: Rng,
+}
+
+impl RandomAgent {
+    /// The number of random programs tried before skipping.
+    const ATTEMPTS: usize = 10;
+
+    /// Initializes a new random agent.
+    pub fn new(rng: Rng) -> Self {
+        Self { rng }
+    }
+}
+
+impl Agent for RandomAgent {
+    fn name(&self) -> &str {
+        "random"
+    }
+
+    fn choose(&mut self, board: &GameBoard, rules: &SimRules) -> String {
+        const CHARS: [char; 4] = ['+', '-', '<', '>'];
+
+        for _ in 0..Self::ATTEMPTS {
+            let len = self.rng.below(board.max_len() as usize + 1);
+            let code: String = (0..len).map(|_| CHARS[self.rng.below(4)]).collect();
+
+            if rules.is_valid(board, &code) {
+                return code;
+            }
+        }
+
+        String::new()
+    }
+}
+
+/// An agent that walks to some bucket and fills it with as many counters as
+/// it can, picking whichever bucket gets it the most locks.
+pub struct GreedyAgent {
+    /// The random number generator, used to break ties.
+    rng: Rng,
+}
+
+impl GreedyAgent {
+    /// Initializes a new greedy agent.
+    pub fn new(rng: Rng) -> Self {
+        Self { rng }
+    }
+
+    /// Scores the position from the point of view of the given player.
+    fn score(board: &GameBoard, player: Player) -> (isize, usize) {
+        let mut locks = 0;
+        let mut counters = 0;
+
+        for bucket in &board.buckets {
+            match bucket.owner {
+                Some(owner) if owner == player => locks += 1,
+                Some(_) => locks -= 1,
+                None => {}
+            }
+
+            counters += bucket.counters.iter().filter(|&&c| c == player).count();
+        }
+
+        (locks, counters)
+    }
+}
+
+impl Agent for GreedyAgent {
+    fn name(&self) -> &str {
+        "greedy"
+    }
+
+    fn choose(&mut self, board: &GameBoard, rules: &SimRules) -> String {
+        let player = board.player();
+        let max_len = board.max_len() as usize;
+
+        let mut best = Vec::new();
+        let mut best_score = None;
+
+        for (idx, bucket) in board.buckets.iter().enumerate() {
+            if bucket.locked {
+                continue;
+            }
+
+            let distance = idx.abs_diff(board.position);
+            if distance >= max_len {
+                continue;
+            }
+
+            let step = if idx < board.position { "<" } else { ">" };
+            let pushes = bucket.free().min(max_len - distance);
+            let code = step.repeat(distance) + &"+".repeat(pushes);
+
+            let mut clone = board.clone();
+            if rules.play(&mut clone, &code).is_err() {
+                continue;
+            }
+
+            let score = Self::score(&clone, player);
+            match best_score {
+                Some(best_score) if best_score > score => {}
+                Some(best_score) if best_score == score => best.push(code),
+                _ => {
+                    best_score = Some(score);
+                    best = vec![code];
+                }
+            }
+        }
+
+        if best.is_empty() {
+            String::new()
+        } else {
+            let idx = self.rng.below(best.len());
+            best.swap_remove(idx)
+        }
+    }
+}
+
+/// The names of the built-in agents.
+pub const AGENTS: [&str; 2] = ["greedy", "random"];
+
+/// Initializes the built-in agent with the given name, or returns `None` if
+/// there's no such agent.
+pub fn new_agent(name: &str, rng: Rng) -> Option<Box<dyn Agent>> {
+    match name {
+        "greedy" => Some(Box::new(GreedyAgent::new(rng))),
+        "random" => Some(Box::new(RandomAgent::new(rng))),
+        _ => None,
+    }
+}
+
+/// The results of a batch of simulated games.
+#[derive(Clone, Debug, Default)]
+pub struct SimStats {
+    /// The number of games played.
+    pub games: usize,
+
+    /// The number of games won outright by each seat.
+    pub wins: Vec<usize>,
+
+    /// The number of games that ended in a tie.
+    pub ties: usize,
+
+    /// The number of games abandoned after [`SIM_MAX_TURNS`] turns.
+    pub unfinished: usize,
+
+    /// The total number of turns over all finished games.
+    pub total_turns: u64,
+
+    /// The number of turns in the longest finished game.
+    pub longest: u32,
+
+    /// The symbols of the players in each seat.
+    pub players: Vec<Player>,
+
+    /// The names of the agents in each seat.
+    pub agents: Vec<String>,
+}
+
+impl Display for SimStats {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let percent = |n: usize| 100.0 * n as f64 / self.games.max(1) as f64;
+
+        writeln!(f, "Simulated {} games.", self.games)?;
+
+        for (seat, ((player, agent), &wins)) in self
+            .players
+            .iter()
+            .zip(&self.agents)
+            .zip(&self.wins)
+            .enumerate()
+        {
+            writeln!(
+                f,
+                "Seat {} ({}, {}): {:.1}% wins",
+                seat + 1,
+                player,
+                agent,
+                percent(wins)
+            )?;
+        }
+
+        writeln!(f, "Ties: {:.1}%", percent(self.ties))?;
+
+        if self.unfinished != 0 {
+            writeln!(
+                f,
+                "Unfinished after {} turns: {:.1}%",
+                SIM_MAX_TURNS,
+                percent(self.unfinished)
+            )?;
+        }
+
+        let finished = self.games - self.unfinished;
+        if finished != 0 {
+            writeln!(
+                f,
+                "Average game length: {:.1} turns",
+                self.total_turns as f64 / finished as f64
+            )?;
+            write!(f, "Longest game: {} turns", self.longest)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Plays `n` complete games between the given agents, starting from the given
+/// board and following the given rules. The agent at index `i` plays in seat
+/// `i`, cycling if there are fewer agents than seats.
+pub fn simulate(
+    mut board: GameBoard,
+    mut agents: Vec<Box<dyn Agent>>,
+    n: usize,
+    rules: &SimRules,
+) -> SimStats {
+    assert!(!agents.is_empty(), "at least one agent must be given");
+    board.reset();
+
+    let player_count = board.player_count();
+    let mut stats = SimStats {
+        wins: vec![0; player_count],
+        players: board.players.iter().copied().collect(),
+        agents: (0..player_count)
+            .map(|seat| agents[seat % agents.len()].name().to_owned())
+            .collect(),
+        ..Default::default()
+    };
+
+    for _ in 0..n {
+        let mut game = board.clone();
+        stats.games += 1;
+
+        loop {
+            if let Some(winners) = game.winners() {
+                stats.total_turns += u64::from(game.turn);
+                stats.longest = stats.longest.max(game.turn);
+
+                if let Some(winner) = winners.sole_winner() {
+                    let seat = game.players.iter().position(|&p| p == winner).unwrap();
+                    stats.wins[seat] += 1;
+                } else {
+                    stats.ties += 1;
+                }
+
+                break;
+            }
+
+            if game.turn >= SIM_MAX_TURNS {
+                stats.unfinished += 1;
+                break;
+            }
+
+            let agent_idx = game.player_idx() % agents.len();
+            let code = agents[agent_idx].choose(&game, rules);
+
+            // Invalid moves are replaced by skips.
+            if rules.play(&mut game, &code).is_err() {
+                rules.play(&mut game, "").unwrap();
+            }
+        }
+    }
+
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::BfCharset;
+
+    /// Plays a few seeded games between the given agents on a small board.
+    fn run(names: &[&str], rules: &SimRules) -> SimStats {
+        let agents = names
+            .iter()
+            .zip(1..)
+            .map(|(name, seed)| new_agent(name, Rng::new(seed)).unwrap())
+            .collect();
+
+        simulate(GameBoard::new_symmetric(5, 3, 1), agents, 20, rules)
+    }
+
+    #[test]
+    fn every_game_is_counted() {
+        let rules = SimRules {
+            dialect: Dialect::default(),
+            steps: 1000,
+        };
+
+        for names in [["greedy", "random"], ["random", "random"]].iter() {
+            let stats = run(names, &rules);
+            assert_eq!(stats.games, 20);
+            assert_eq!(
+                stats.wins.iter().sum::<usize>() + stats.ties + stats.unfinished,
+                20
+            );
+            assert_eq!(stats.agents, names.to_vec());
+        }
+    }
+
+    #[test]
+    fn agents_write_in_the_charset() {
+        // Swaps the meanings of + and -, so untranslated moves would fail.
+        let rules = SimRules {
+            dialect: Dialect {
+                charset: BfCharset::custom(['-', '+', '<', '>', '[', ']']).unwrap(),
+                ..Dialect::default()
+            },
+            steps: 1000,
+        };
+
+        let stats = run(&["greedy"], &rules);
+        assert_eq!(stats.unfinished, 0);
+    }
+}
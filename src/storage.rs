@@ -0,0 +1,75 @@
+//! Data kept per guild on disk, so that it survives restarts. Each kind of
+//! data is stored in its own directory, as one JSON file per guild.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serenity::model::id::GuildId;
+
+/// Returns the directory the given kind of data is stored in. This is within
+/// `data`, unless the `DATA_DIR` environment variable says otherwise.
+fn dir(kind: &str) -> PathBuf {
+    env::var_os("DATA_DIR")
+        .map_or_else(|| PathBuf::from("data"), PathBuf::from)
+        .join(kind)
+}
+
+/// Returns the file the given kind of data of a guild is stored in.
+fn path(kind: &str, guild_id: GuildId) -> PathBuf {
+    dir(kind).join(format!("{}.json", guild_id.0))
+}
+
+/// Loads the given kind of data of every guild. Files that can't be read are
+/// skipped, and the error is printed.
+pub fn load_all<T: DeserializeOwned>(kind: &str) -> HashMap<GuildId, T> {
+    let mut res = HashMap::new();
+
+    // Nothing has been saved yet if the directory doesn't exist.
+    let entries = match fs::read_dir(dir(kind)) {
+        Ok(entries) => entries,
+        Err(_) => return res,
+    };
+
+    for path in entries.flatten().map(|entry| entry.path()) {
+        let guild_id = match path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse().ok())
+        {
+            Some(id) => GuildId(id),
+            None => continue,
+        };
+
+        let data = fs::read_to_string(&path)
+            .and_then(|json| serde_json::from_str(&json).map_err(io::Error::from));
+
+        match data {
+            Ok(data) => {
+                res.insert(guild_id, data);
+            }
+            Err(why) => println!("Error loading {}: {}", path.display(), why),
+        }
+    }
+
+    res
+}
+
+/// Saves the given kind of data of a guild, replacing what was there.
+pub fn save<T: Serialize>(kind: &str, guild_id: GuildId, data: &T) -> io::Result<()> {
+    fs::create_dir_all(dir(kind))?;
+    let json = serde_json::to_string_pretty(data)?;
+    fs::write(path(kind, guild_id), json)
+}
+
+/// Deletes the given kind of data of a guild, if it has any.
+pub fn clear(kind: &str, guild_id: GuildId) -> io::Result<()> {
+    match fs::remove_file(path(kind, guild_id)) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        res => res,
+    }
+}
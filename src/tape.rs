@@ -0,0 +1,164 @@
+//! A conventional Brainfuck tape, for running ordinary programs outside of
+//! the game.
+
+use crate::game::{Command, EvalError, EvalResult, Memory};
+
+/// A tape of wrapping byte cells, together with the output written to it.
+#[derive(Clone, Debug)]
+pub struct Tape {
+    /// The cells of the tape.
+    cells: Vec<u8>,
+
+    /// The index of the current cell.
+    pointer: usize,
+
+    /// The bytes written by `.`.
+    output: Vec<u8>,
+}
+
+impl Default for Tape {
+    fn default() -> Self {
+        Self {
+            cells: vec![0; Self::LEN],
+            pointer: 0,
+            output: Vec::new(),
+        }
+    }
+}
+
+impl Tape {
+    /// The number of cells on the tape.
+    pub const LEN: usize = 30_000;
+
+    /// Initializes an empty tape.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the output written so far, with any invalid UTF-8 replaced.
+    pub fn output(&self) -> String {
+        String::from_utf8_lossy(&self.output).into_owned()
+    }
+}
+
+impl Memory for Tape {
+    fn exec_n(&mut self, cmd: Command, count: usize) -> EvalResult<()> {
+        let cell = self.cells[self.pointer];
+
+        match cmd {
+            // Cells wrap around, so only the count modulo 256 matters.
+            Command::Increment => self.cells[self.pointer] = cell.wrapping_add(count as u8),
+            Command::Decrement => self.cells[self.pointer] = cell.wrapping_sub(count as u8),
+            Command::Clear => self.cells[self.pointer] = 0,
+
+            Command::MoveLeft => {
+                self.pointer = self
+                    .pointer
+                    .checked_sub(count)
+                    .ok_or(EvalError::UnderBounds)?;
+            }
+
+            Command::MoveRight => {
+                if count >= Self::LEN - self.pointer {
+                    return Err(EvalError::OverBounds);
+                }
+
+                self.pointer += count;
+            }
+
+            // No cell is ever locked, so this goes to the first one.
+            Command::Seek => self.pointer = 0,
+
+            Command::SwitchTape => return Err(EvalError::SingleTape),
+            Command::MoveUp | Command::MoveDown => return Err(EvalError::NotGrid),
+        }
+
+        Ok(())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.cells[self.pointer] == 0
+    }
+
+    fn is_mine(&self) -> bool {
+        false
+    }
+
+    fn jump_to(&mut self, idx: usize) -> EvalResult<()> {
+        if idx >= Self::LEN {
+            Err(EvalError::OverBounds)
+        } else {
+            self.pointer = idx;
+            Ok(())
+        }
+    }
+
+    fn write_output(&mut self) {
+        self.output.push(self.cells[self.pointer]);
+    }
+
+    /// Sets the current cell to the value, which is a byte of the input, or
+    /// to zero once the input runs out.
+    fn read_input(&mut self, value: Option<usize>) -> EvalResult<()> {
+        self.cells[self.pointer] = value.unwrap_or(0) as u8;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Brainfuck, Dialect};
+
+    /// Runs a program on a new tape with the given input, as `bf` does.
+    fn run(code: &str, input: &str) -> (Tape, EvalResult<u32>) {
+        let dialect = Dialect {
+            io: true,
+            max_nesting: usize::MAX,
+            ..Dialect::default()
+        };
+
+        let mut bf = Brainfuck::with_dialect(code, &dialect).unwrap();
+        bf.set_input(input.bytes().map(usize::from));
+        let mut tape = Tape::new();
+        let res = bf.run(&mut tape, 100_000, None);
+        (tape, res)
+    }
+
+    #[test]
+    fn hello_world() {
+        let (tape, res) = run(
+            "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.",
+            "",
+        );
+
+        assert!(res.is_ok());
+        assert_eq!(tape.output(), "Hello World!\n");
+    }
+
+    #[test]
+    fn cells_wrap() {
+        let (tape, res) = run("-", "");
+        assert!(res.is_ok());
+        assert_eq!(tape.cells[0], 255);
+
+        let (tape, _) = run(&"+".repeat(300), "");
+        assert_eq!(tape.cells[0], 44);
+
+        // Counting down from 0 ends once the cell wraps back to 0.
+        let (tape, res) = run("-[-]>+", "");
+        assert_eq!(res, Ok(1 + 3 * 255 + 2));
+        assert_eq!(tape.cells[..2], [0, 1]);
+    }
+
+    #[test]
+    fn input_runs_out() {
+        let (tape, res) = run(",[.,]", "ab");
+        assert!(res.is_ok());
+        assert_eq!(tape.output(), "ab");
+
+        // Once the input runs out, reads give 0.
+        let (tape, _) = run(",>,>,", "x");
+        assert_eq!(tape.cells[..3], [b'x', 0, 0]);
+    }
+}